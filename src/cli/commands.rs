@@ -18,4 +18,35 @@ pub struct Args {
         help = "Input CSV file with columns: type, client, tx, amount"
     )]
     pub input_file: PathBuf,
+
+    /// Path to a TOML file overriding the engine's business rules
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "TOML policy file overriding the default business rules"
+    )]
+    pub policy: Option<PathBuf>,
+
+    /// Path to a CSV file with the exchange rates of currency conversions
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "CSV rate table with columns: base, quote, rate, spread, from, from_row"
+    )]
+    pub rates: Option<PathBuf>,
+
+    /// Path to write the JSON processing report to
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write a JSON report of the processed and rejected rows to FILE, - for stderr"
+    )]
+    pub report: Option<PathBuf>,
+
+    /// Export every balance column instead of the basic ones
+    #[arg(
+        long,
+        help = "Export the pending, authorized, reserved, headroom and status columns too"
+    )]
+    pub extended: bool,
 }
@@ -3,7 +3,8 @@ mod commands;
 use anyhow::{Context, Result};
 use clap::Parser;
 use commands::Args;
-use payment_engine::PaymentEngine;
+use payment_engine::{EnginePolicy, ExportFormat, PaymentEngine, ProcessingReport, RateTable};
+use std::path::Path;
 
 fn main() -> Result<()> {
     // Parse the CLI arguments
@@ -12,29 +13,75 @@ fn main() -> Result<()> {
     // Initialize logger with default level of warn (can be overridden with RUST_LOG)
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
-    // 1. Initialize the PaymentEngine
-    let mut engine = PaymentEngine::new();
+    // 1. Initialize the PaymentEngine with the default or the given policy and rates
+    let policy = match &args.policy {
+        Some(path) => {
+            log::info!("Loading policy from {}", path.display());
+            let policy = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read policy file: {}", path.display()))?;
+            EnginePolicy::from_toml(&policy)
+                .with_context(|| format!("Failed to parse policy file: {}", path.display()))?
+        }
+        None => EnginePolicy::default(),
+    };
+    let rates = match &args.rates {
+        Some(path) => {
+            log::info!("Loading exchange rates from {}", path.display());
+            let file = std::fs::File::open(path)
+                .with_context(|| format!("Failed to open rates file: {}", path.display()))?;
+            RateTable::from_csv(file)
+                .with_context(|| format!("Failed to parse rates file: {}", path.display()))?
+        }
+        None => RateTable::default(),
+    };
+    let mut engine = PaymentEngine::builder().policy(policy).rates(rates).build();
 
     // 2. Open and process the input file
     log::info!("Processing transactions from {}", args.input_file.display());
     let file = std::fs::File::open(&args.input_file)
         .with_context(|| format!("Failed to open input file: {}", args.input_file.display()))?;
 
-    engine
+    let report = engine
         .process_transactions(file)
         .context("Failed to process transactions")?;
 
+    if let Some(path) = &args.report {
+        write_report(path, &report)?;
+    }
+
     log::info!(
         "Processing complete, exporting {} accounts",
         engine.account_count()
     );
 
     // 3. Export the accounts to stdout
+    let format = if args.extended {
+        ExportFormat::Extended
+    } else {
+        ExportFormat::Basic
+    };
     engine
-        .export_accounts(std::io::stdout())
+        .export_accounts_as(std::io::stdout(), format)
         .context("Failed to export accounts to stdout")?;
 
     log::info!("Export complete");
 
     Ok(())
 }
+
+/// Write the processing report as JSON to `path`, or to stderr for `-`, keeping stdout for the
+/// accounts
+fn write_report(path: &Path, report: &ProcessingReport) -> Result<()> {
+    if path == Path::new("-") {
+        serde_json::to_writer_pretty(std::io::stderr(), report)
+            .context("Failed to write report to stderr")?;
+        eprintln!();
+        return Ok(());
+    }
+    log::info!("Writing processing report to {}", path.display());
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create report file: {}", path.display()))?;
+    serde_json::to_writer_pretty(file, report)
+        .with_context(|| format!("Failed to write report file: {}", path.display()))?;
+    Ok(())
+}
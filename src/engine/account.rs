@@ -1,36 +1,182 @@
+use std::collections::BTreeSet;
+
+use super::currency::Currency;
+use super::ledger::{Bucket, JournalEntry, LedgerAccount};
+use super::status::{AccountStatus, FreezeReason, Restriction};
 use super::Decimal;
 use serde::{Deserialize, Serialize, Serializer};
 
 pub type ClientId = u16;
 
-/// Serialize Decimal with exactly 4 decimal places
+/// Serialize Decimal with the decimal places it was rescaled to, see `export_scale`
+fn serialize_decimal<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Decimal places of exported balances: 4, or the scale of currencies that are finer
 /// NOTE: this cancels the `normalize()` impact in the CSV output
-fn serialize_decimal_4dp<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
-    serializer.serialize_str(&format!("{value:.4}"))
+fn export_scale(currency: Currency) -> u32 {
+    currency.scale().max(4)
+}
+
+/// Returns `value` with exactly `scale` decimal places
+fn rescaled(mut value: Decimal, scale: u32) -> Decimal {
+    value.rescale(scale);
+    value
 }
 
-/// Represents a client's account with available, held, and total balances.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// Represents a client's account in one currency with available, held, pending, authorized and
+/// reserved balances.
+///
+/// The balances are derived from journal entries: every balance operation returns the entry
+/// it applied, which the engine posts to its `Ledger`. The total is the sum of all balances.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(into = "AccountRow", try_from = "AccountRow")]
 pub struct Account {
-    #[serde(rename = "client")]
     client_id: ClientId,
-    #[serde(serialize_with = "serialize_decimal_4dp")]
+    currency: Currency,
+    available: Decimal,
+    held: Decimal,
+    /// Deposited funds that have not cleared yet
+    pending: Decimal,
+    /// Funds reserved by authorized withdrawals until they are captured or voided
+    authorized: Decimal,
+    /// Part of deposits kept in a rolling reserve until it is released
+    reserved: Decimal,
+    /// Overdraft granted to the client, available funds may go down to `-credit_limit`
+    credit_limit: Decimal,
+    /// Active freezes, each one lifted independently
+    freezes: BTreeSet<FreezeReason>,
+    /// Operator restriction, applies while the account is neither frozen nor closed
+    restriction: Restriction,
+    closed: bool,
+}
+
+/// Columns written by `PaymentEngine::export_accounts_as`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `client,currency,available,held,total,locked`, one row per client and currency
+    #[default]
+    Basic,
+    /// Every balance and the status of an account, the columns `Account` (de)serializes
+    /// `client,currency,available,held,pending,authorized,reserved,total,headroom,locked,status`
+    Extended,
+}
+
+/// Basic CSV representation of an `Account`, see `ExportFormat::Basic`.
+#[derive(Serialize)]
+pub(super) struct BasicAccountRow {
+    client: ClientId,
+    currency: Currency,
+    #[serde(serialize_with = "serialize_decimal")]
+    available: Decimal,
+    #[serde(serialize_with = "serialize_decimal")]
+    held: Decimal,
+    #[serde(serialize_with = "serialize_decimal")]
+    total: Decimal,
+    locked: bool,
+}
+
+impl From<&Account> for BasicAccountRow {
+    fn from(account: &Account) -> Self {
+        let scale = export_scale(account.currency);
+        Self {
+            client: account.client_id,
+            currency: account.currency,
+            available: rescaled(account.available, scale),
+            held: rescaled(account.held, scale),
+            total: rescaled(account.total(), scale),
+            locked: account.status().is_locked(),
+        }
+    }
+}
+
+/// Flat CSV representation of an `Account`, see `ExportFormat::Extended`.
+#[derive(Serialize, Deserialize)]
+struct AccountRow {
+    client: ClientId,
+    currency: Currency,
+    #[serde(serialize_with = "serialize_decimal")]
     available: Decimal,
-    #[serde(serialize_with = "serialize_decimal_4dp")]
+    #[serde(serialize_with = "serialize_decimal")]
     held: Decimal,
-    #[serde(serialize_with = "serialize_decimal_4dp")]
+    #[serde(serialize_with = "serialize_decimal")]
+    pending: Decimal,
+    #[serde(serialize_with = "serialize_decimal")]
+    authorized: Decimal,
+    #[serde(serialize_with = "serialize_decimal")]
+    reserved: Decimal,
+    #[serde(serialize_with = "serialize_decimal")]
     total: Decimal,
+    /// Funds that can still be debited, including the credit limit
+    #[serde(serialize_with = "serialize_decimal")]
+    headroom: Decimal,
     locked: bool,
+    status: String,
+}
+
+impl From<Account> for AccountRow {
+    fn from(account: Account) -> Self {
+        let status = account.status();
+        let scale = export_scale(account.currency);
+        Self {
+            client: account.client_id,
+            currency: account.currency,
+            available: rescaled(account.available, scale),
+            held: rescaled(account.held, scale),
+            pending: rescaled(account.pending, scale),
+            authorized: rescaled(account.authorized, scale),
+            reserved: rescaled(account.reserved, scale),
+            total: rescaled(account.total(), scale),
+            headroom: rescaled(account.headroom(), scale),
+            locked: status.is_locked(),
+            status: status.to_string(),
+        }
+    }
+}
+
+impl TryFrom<AccountRow> for Account {
+    type Error = String;
+
+    fn try_from(row: AccountRow) -> Result<Self, Self::Error> {
+        let mut account = Account::new(row.client, row.currency);
+        account.available = row.available;
+        account.held = row.held;
+        account.pending = row.pending;
+        account.authorized = row.authorized;
+        account.reserved = row.reserved;
+        account.credit_limit = row.headroom - row.available;
+        if account.total() != row.total {
+            return Err(format!(
+                "total {} of client {} does not match its balances",
+                row.total, row.client
+            ));
+        }
+        match row.status.parse::<AccountStatus>()? {
+            AccountStatus::Active => {}
+            AccountStatus::WithdrawOnly => account.restriction = Restriction::WithdrawOnly,
+            AccountStatus::DepositOnly => account.restriction = Restriction::DepositOnly,
+            AccountStatus::Frozen(reasons) => account.freezes = reasons,
+            AccountStatus::Closed => account.closed = true,
+        }
+        Ok(account)
+    }
 }
 
 impl Account {
-    pub(super) fn new(client_id: ClientId) -> Self {
+    pub(super) fn new(client_id: ClientId, currency: Currency) -> Self {
         Self {
             client_id,
+            currency,
             available: Decimal::ZERO,
             held: Decimal::ZERO,
-            total: Decimal::ZERO,
-            locked: false,
+            pending: Decimal::ZERO,
+            authorized: Decimal::ZERO,
+            reserved: Decimal::ZERO,
+            credit_limit: Decimal::ZERO,
+            freezes: BTreeSet::new(),
+            restriction: Restriction::None,
+            closed: false,
         }
     }
 
@@ -39,6 +185,11 @@ impl Account {
         self.client_id
     }
 
+    /// Returns the currency of the account
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
     /// Returns the available balance
     pub fn available(&self) -> Decimal {
         self.available
@@ -49,42 +200,259 @@ impl Account {
         self.held
     }
 
-    /// Returns the total balance
+    /// Returns the balance of deposits that have not cleared yet
+    pub fn pending(&self) -> Decimal {
+        self.pending
+    }
+
+    /// Returns the balance reserved by authorized withdrawals
+    pub fn authorized(&self) -> Decimal {
+        self.authorized
+    }
+
+    /// Returns the balance kept in the rolling reserve
+    pub fn reserved(&self) -> Decimal {
+        self.reserved
+    }
+
+    /// Returns the total balance, the sum of all balances
     pub fn total(&self) -> Decimal {
-        self.total
+        self.balances().iter().sum()
+    }
+
+    /// Returns the credit limit, available funds may go down to its negative
+    pub fn credit_limit(&self) -> Decimal {
+        self.credit_limit
     }
 
-    /// Returns whether the account is locked (frozen due to chargeback)
+    /// Returns the funds that can still be debited: available funds plus the credit limit
+    pub fn headroom(&self) -> Decimal {
+        self.available + self.credit_limit
+    }
+
+    /// Returns the effective account status
+    pub fn status(&self) -> AccountStatus {
+        if self.closed {
+            AccountStatus::Closed
+        } else if !self.freezes.is_empty() {
+            AccountStatus::Frozen(self.freezes.clone())
+        } else {
+            match self.restriction {
+                Restriction::None => AccountStatus::Active,
+                Restriction::WithdrawOnly => AccountStatus::WithdrawOnly,
+                Restriction::DepositOnly => AccountStatus::DepositOnly,
+            }
+        }
+    }
+
+    /// Returns whether the account is locked (frozen for any reason, or closed)
     pub fn is_locked(&self) -> bool {
-        self.locked
+        self.closed || !self.freezes.is_empty()
+    }
+
+    /// Returns whether the account is frozen for the given reason
+    pub fn is_frozen_for(&self, reason: FreezeReason) -> bool {
+        self.freezes.contains(&reason)
+    }
+
+    /// Returns whether the account was closed
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Returns the operator restriction of the account
+    pub(super) fn restriction(&self) -> Restriction {
+        self.restriction
+    }
+
+    /// Returns whether the account holds no funds at all
+    pub fn is_empty(&self) -> bool {
+        self.balances().iter().all(Decimal::is_zero)
+    }
+
+    /// Freeze the account for the given reason.
+    pub(super) fn freeze(&mut self, reason: FreezeReason) {
+        self.freezes.insert(reason);
+    }
+
+    /// Lift the freeze for the given reason, other freezes stay in place.
+    ///
+    /// # Panics (debug only)
+    /// Panics if called on a closed account.
+    pub(super) fn unfreeze(&mut self, reason: FreezeReason) {
+        debug_assert!(!self.closed, "unfreeze called on closed account");
+        self.freezes.remove(&reason);
+    }
+
+    /// Take over the freezes, restriction and closure of another account of the same client,
+    /// so a newly opened currency account shares the status of the client's other accounts.
+    pub(super) fn inherit_status(&mut self, sibling: &Account) {
+        self.freezes.clone_from(&sibling.freezes);
+        self.restriction = sibling.restriction;
+        self.closed = sibling.closed;
+    }
+
+    /// Set the credit limit. Lowering it below the current overdraft leaves a negative
+    /// headroom, further debits are rejected until the account is back within the limit.
+    pub(super) fn set_credit_limit(&mut self, limit: Decimal) {
+        self.credit_limit = limit.normalize();
+    }
+
+    /// Restrict the transactions the account accepts while it is not frozen.
+    pub(super) fn restrict(&mut self, restriction: Restriction) {
+        self.restriction = restriction;
+    }
+
+    /// Permanently close the account. Closed accounts are locked as well.
+    ///
+    /// # Panics (debug only)
+    /// Panics if the account still holds funds.
+    pub(super) fn close(&mut self) {
+        debug_assert!(self.is_empty(), "close called on account holding funds");
+        self.closed = true;
     }
 
     /// Credit the account with a deposit amount.
     /// Increases both available and total funds.
+    /// Whether locked accounts accept deposits is decided by the engine policy.
+    pub(super) fn deposit(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            LedgerAccount::Settlement,
+            self.bucket(Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Credit the account with a deposit that has not cleared yet.
+    /// Increases pending and total funds, the funds can't be withdrawn until they clear.
+    pub(super) fn deposit_uncleared(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            LedgerAccount::Settlement,
+            self.bucket(Bucket::Pending),
+            amount,
+        )
+    }
+
+    /// Clear a previously uncleared deposit.
+    /// Moves funds from pending to available. Total remains unchanged.
+    /// Allowed on locked accounts, clearing does not bring new funds into the account.
+    pub(super) fn clear(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            self.bucket(Bucket::Pending),
+            self.bucket(Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Credit the reserved part of a deposit.
+    /// Increases reserved and total funds, the funds can't be withdrawn until they are released.
+    pub(super) fn deposit_reserved(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            LedgerAccount::Settlement,
+            self.bucket(Bucket::Reserved),
+            amount,
+        )
+    }
+
+    /// Release reserved funds.
+    /// Moves funds from reserved to available. Total remains unchanged.
+    /// Allowed on locked accounts, releasing does not bring new funds into the account.
+    pub(super) fn release_reserve(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            self.bucket(Bucket::Reserved),
+            self.bucket(Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Debit the account with a withdrawal amount.
+    /// Caller must ensure sufficient funds (within the credit limit) and unlocked account.
     ///
     /// # Panics (debug only)
     /// Panics if called on a locked account.
-    pub(super) fn deposit(&mut self, amount: Decimal) {
-        debug_assert!(!self.locked, "deposit called on locked account");
-        self.available += amount;
-        self.total += amount;
-        self.normalize();
-        #[cfg(debug_assertions)]
-        self.assert_invariant();
+    pub(super) fn withdraw(&mut self, amount: Decimal) -> JournalEntry {
+        debug_assert!(!self.is_locked(), "withdraw called on locked account");
+        self.post(
+            self.bucket(Bucket::Available),
+            LedgerAccount::Settlement,
+            amount,
+        )
     }
 
-    /// Debit the account with a withdrawal amount.
+    /// Apply an operator adjustment, a positive amount credits and a negative amount debits
+    /// available and total funds.
+    /// Whether frozen or restricted accounts accept adjustments is decided by the engine policy.
+    pub(super) fn adjust(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            LedgerAccount::Adjustments,
+            self.bucket(Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Debit the source side of a currency conversion.
+    /// Decreases available and total funds, caller must ensure sufficient funds and unlocked
+    /// account.
+    ///
+    /// # Panics (debug only)
+    /// Panics if called on a locked account.
+    pub(super) fn convert_out(&mut self, amount: Decimal) -> JournalEntry {
+        debug_assert!(!self.is_locked(), "convert_out called on locked account");
+        self.post(
+            self.bucket(Bucket::Available),
+            LedgerAccount::Exchange,
+            amount,
+        )
+    }
+
+    /// Credit the target side of a currency conversion.
+    /// Increases available and total funds.
+    pub(super) fn convert_in(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            LedgerAccount::Exchange,
+            self.bucket(Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Authorize a withdrawal.
+    /// Moves funds from available to authorized. Total remains unchanged.
     /// Caller must ensure sufficient funds and unlocked account.
     ///
     /// # Panics (debug only)
     /// Panics if called on a locked account.
-    pub(super) fn withdraw(&mut self, amount: Decimal) {
-        debug_assert!(!self.locked, "withdraw called on locked account");
-        self.available -= amount;
-        self.total -= amount;
-        self.normalize();
-        #[cfg(debug_assertions)]
-        self.assert_invariant();
+    pub(super) fn authorize(&mut self, amount: Decimal) -> JournalEntry {
+        debug_assert!(!self.is_locked(), "authorize called on locked account");
+        self.post(
+            self.bucket(Bucket::Available),
+            self.bucket(Bucket::Authorized),
+            amount,
+        )
+    }
+
+    /// Capture an authorized withdrawal.
+    /// Removes the authorized funds from total, the money leaves the account.
+    ///
+    /// # Panics (debug only)
+    /// Panics if called on a locked account.
+    pub(super) fn capture(&mut self, amount: Decimal) -> JournalEntry {
+        debug_assert!(!self.is_locked(), "capture called on locked account");
+        self.post(
+            self.bucket(Bucket::Authorized),
+            LedgerAccount::Settlement,
+            amount,
+        )
+    }
+
+    /// Void an authorized withdrawal.
+    /// Moves the authorized funds back to available. Total remains unchanged.
+    /// Allowed on locked accounts so authorized withdrawals can still be released.
+    pub(super) fn void(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            self.bucket(Bucket::Authorized),
+            self.bucket(Bucket::Available),
+            amount,
+        )
     }
 
     /// Hold funds for a dispute.
@@ -93,75 +461,154 @@ impl Account {
     ///
     /// # Panics (debug only)
     /// Panics if called on a locked account.
-    pub(super) fn hold(&mut self, amount: Decimal) {
-        debug_assert!(!self.locked, "hold called on locked account");
-        self.available -= amount;
-        self.held += amount;
-        self.normalize();
-        #[cfg(debug_assertions)]
-        self.assert_invariant();
+    pub(super) fn hold(&mut self, amount: Decimal) -> JournalEntry {
+        debug_assert!(!self.is_locked(), "hold called on locked account");
+        self.post(
+            self.bucket(Bucket::Available),
+            self.bucket(Bucket::Held),
+            amount,
+        )
     }
 
     /// Release held funds (resolve a dispute).
     /// Moves funds from held back to available. Total remains unchanged.
-    ///
-    /// # Panics (debug only)
-    /// Panics if called on a locked account.
-    pub(super) fn release(&mut self, amount: Decimal) {
-        debug_assert!(!self.locked, "release called on locked account");
-        self.held -= amount;
-        self.available += amount;
-        self.normalize();
-        #[cfg(debug_assertions)]
-        self.assert_invariant();
+    /// Allowed on locked accounts so open disputes can still be settled.
+    pub(super) fn release(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            self.bucket(Bucket::Held),
+            self.bucket(Bucket::Available),
+            amount,
+        )
     }
 
     /// Process a chargeback.
-    /// Removes held funds from total and freezes the account.
+    /// Removes held funds from total and freezes the account if `lock` is set.
+    /// Allowed on locked accounts so open disputes can still be settled.
+    pub(super) fn chargeback(&mut self, amount: Decimal, lock: bool) -> JournalEntry {
+        if lock {
+            self.freeze(FreezeReason::Chargeback);
+        }
+        self.post(
+            self.bucket(Bucket::Held),
+            LedgerAccount::Chargebacks,
+            amount,
+        )
+    }
+
+    /// Process a transfer chargeback.
+    /// Moves held funds straight to the sending client's available funds, the money stays with
+    /// the engine's clients. The returned entry must be applied to the sender's account too.
+    /// Allowed on locked accounts so open disputes can still be settled.
+    pub(super) fn chargeback_transfer(
+        &mut self,
+        sender: ClientId,
+        amount: Decimal,
+    ) -> JournalEntry {
+        self.post(
+            self.bucket(Bucket::Held),
+            LedgerAccount::Client(sender, Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Hold funds for a disputed withdrawal.
+    /// Provisionally credits the withdrawn amount into held funds, increasing held and total.
     ///
     /// # Panics (debug only)
     /// Panics if called on a locked account.
-    pub(super) fn chargeback(&mut self, amount: Decimal) {
-        debug_assert!(!self.locked, "chargeback called on locked account");
-        self.held -= amount;
-        self.total -= amount;
+    pub(super) fn hold_reversal(&mut self, amount: Decimal) -> JournalEntry {
+        debug_assert!(!self.is_locked(), "hold_reversal called on locked account");
+        self.post(
+            LedgerAccount::Chargebacks,
+            self.bucket(Bucket::Held),
+            amount,
+        )
+    }
+
+    /// Cancel a held withdrawal reversal (resolve a withdrawal dispute).
+    /// The withdrawal stands, so the provisional credit is removed from held and total.
+    /// Allowed on locked accounts so open disputes can still be settled.
+    pub(super) fn cancel_reversal(&mut self, amount: Decimal) -> JournalEntry {
+        self.post(
+            self.bucket(Bucket::Held),
+            LedgerAccount::Chargebacks,
+            amount,
+        )
+    }
+
+    /// Process a withdrawal chargeback.
+    /// Credits the held reversal back to available funds and freezes the account if `lock` is set.
+    /// Allowed on locked accounts so open disputes can still be settled.
+    pub(super) fn chargeback_reversal(&mut self, amount: Decimal, lock: bool) -> JournalEntry {
+        if lock {
+            self.freeze(FreezeReason::Chargeback);
+        }
+        self.post(
+            self.bucket(Bucket::Held),
+            self.bucket(Bucket::Available),
+            amount,
+        )
+    }
+
+    /// Returns the ledger account of one of the account's balance buckets
+    fn bucket(&self, bucket: Bucket) -> LedgerAccount {
+        LedgerAccount::Client(self.client_id, bucket)
+    }
+
+    /// Apply a journal entry that moves `amount` from `from` to `to` and return it,
+    /// so the caller can post it to the ledger.
+    fn post(&mut self, from: LedgerAccount, to: LedgerAccount, amount: Decimal) -> JournalEntry {
+        let entry = JournalEntry::transfer(self.currency, from, to, amount);
+        self.apply(&entry);
+        entry
+    }
+
+    /// Derive the balances from a journal entry: every posting to one of the account's buckets
+    /// changes the balance of that bucket. Entries in other currencies are ignored.
+    pub(super) fn apply(&mut self, entry: &JournalEntry) {
+        if entry.currency() != self.currency {
+            return;
+        }
+        for posting in entry.postings() {
+            if let LedgerAccount::Client(client_id, bucket) = posting.account() {
+                if client_id == self.client_id {
+                    *self.balance_mut(bucket) += posting.amount();
+                }
+            }
+        }
         self.normalize();
-        self.locked = true;
-        #[cfg(debug_assertions)]
-        self.assert_invariant();
-    }
-
-    /// Assert the fundamental accounting invariant:
-    /// total = available + held (This should )
-    /// available = total - held
-    /// held = total - available
-    #[cfg(debug_assertions)]
-    fn assert_invariant(&self) {
-        debug_assert_eq!(
-            self.total,
-            self.available + self.held,
-            "Invariant violated: total ({}) != available ({}) + held ({})",
-            self.total,
-            self.available,
-            self.held
-        );
-        // Anything below this line should not panic because the previous check should catch it
-        debug_assert_eq!(
-            self.available,
-            self.total - self.held,
-            "Invariant violated: available ({}) != total ({}) - held ({})",
+    }
+
+    /// Returns the balance of one of the account's buckets
+    pub fn balance(&self, bucket: Bucket) -> Decimal {
+        match bucket {
+            Bucket::Available => self.available,
+            Bucket::Held => self.held,
+            Bucket::Pending => self.pending,
+            Bucket::Authorized => self.authorized,
+            Bucket::Reserved => self.reserved,
+        }
+    }
+
+    fn balance_mut(&mut self, bucket: Bucket) -> &mut Decimal {
+        match bucket {
+            Bucket::Available => &mut self.available,
+            Bucket::Held => &mut self.held,
+            Bucket::Pending => &mut self.pending,
+            Bucket::Authorized => &mut self.authorized,
+            Bucket::Reserved => &mut self.reserved,
+        }
+    }
+
+    /// Every balance bucket the total is split into
+    fn balances(&self) -> [Decimal; 5] {
+        [
             self.available,
-            self.total,
-            self.held
-        );
-        debug_assert_eq!(
-            self.held,
-            self.total - self.available,
-            "Invariant violated: held ({}) != total ({}) - available ({})",
             self.held,
-            self.total,
-            self.available
-        );
+            self.pending,
+            self.authorized,
+            self.reserved,
+        ]
     }
 
     /// Normalize all decimal fields to trim trailing zeros.
@@ -169,18 +616,21 @@ impl Account {
     fn normalize(&mut self) {
         self.available = self.available.normalize();
         self.held = self.held.normalize();
-        self.total = self.total.normalize();
+        self.pending = self.pending.normalize();
+        self.authorized = self.authorized.normalize();
+        self.reserved = self.reserved.normalize();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::ledger::Ledger;
     use rust_decimal_macros::dec;
 
     #[test]
     fn test_new_account_has_zero_balances() {
-        let account = Account::new(1);
+        let account = Account::new(1, Currency::default());
         assert_eq!(account.available(), Decimal::ZERO);
         assert_eq!(account.held(), Decimal::ZERO);
         assert_eq!(account.total(), Decimal::ZERO);
@@ -189,8 +639,9 @@ mod tests {
 
     #[test]
     fn test_deposit_increases_available_and_total() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(100.5));
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100.5)));
 
         assert_eq!(account.available(), dec!(100.5));
         assert_eq!(account.total(), dec!(100.5));
@@ -199,9 +650,10 @@ mod tests {
 
     #[test]
     fn test_withdraw_decreases_available_and_total() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(100));
-        account.withdraw(dec!(40));
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.withdraw(dec!(40)));
 
         assert_eq!(account.available(), dec!(60));
         assert_eq!(account.total(), dec!(60));
@@ -209,9 +661,10 @@ mod tests {
 
     #[test]
     fn test_hold_moves_funds_from_available_to_held() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(100));
-        account.hold(dec!(30));
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.hold(dec!(30)));
 
         assert_eq!(account.available(), dec!(70));
         assert_eq!(account.held(), dec!(30));
@@ -220,9 +673,10 @@ mod tests {
 
     #[test]
     fn test_hold_allows_negative_available() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(50));
-        account.hold(dec!(100)); // hold more than available (dispute after withdrawal)
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(50)));
+        ledger.post(account.hold(dec!(100))); // hold more than available (dispute after withdrawal)
 
         // Per spec: available decreases by disputed amount (can go negative)
         assert_eq!(account.available(), dec!(-50));
@@ -232,10 +686,11 @@ mod tests {
 
     #[test]
     fn test_release_moves_funds_from_held_to_available() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(100));
-        account.hold(dec!(30));
-        account.release(dec!(30));
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.hold(dec!(30)));
+        ledger.post(account.release(dec!(30)));
 
         assert_eq!(account.available(), dec!(100));
         assert_eq!(account.held(), Decimal::ZERO);
@@ -244,10 +699,11 @@ mod tests {
 
     #[test]
     fn test_chargeback_removes_held_funds_and_locks_account() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(100));
-        account.hold(dec!(30));
-        account.chargeback(dec!(30));
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.hold(dec!(30)));
+        ledger.post(account.chargeback(dec!(30), true));
 
         assert_eq!(account.available(), dec!(70)); // unchanged from after hold
         assert_eq!(account.held(), Decimal::ZERO);
@@ -255,10 +711,290 @@ mod tests {
         assert!(account.is_locked());
     }
 
+    #[test]
+    fn test_settle_disputes_on_locked_account() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.deposit(dec!(50)));
+        ledger.post(account.hold(dec!(100)));
+        ledger.post(account.hold(dec!(50)));
+        ledger.post(account.chargeback(dec!(100), true));
+        assert!(account.is_locked());
+
+        ledger.post(account.release(dec!(20)));
+        ledger.post(account.chargeback(dec!(30), true));
+        assert_eq!(account.available(), dec!(20));
+        assert_eq!(account.held(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(20));
+    }
+
+    #[test]
+    fn test_chargeback_without_lock() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.hold(dec!(30)));
+        ledger.post(account.chargeback(dec!(30), false));
+
+        assert_eq!(account.total(), dec!(70));
+        assert!(!account.is_locked());
+    }
+
+    #[test]
+    fn test_withdrawal_dispute_resolve_restores_balances() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.withdraw(dec!(40)));
+        ledger.post(account.hold_reversal(dec!(40)));
+
+        assert_eq!(account.available(), dec!(60));
+        assert_eq!(account.held(), dec!(40));
+        assert_eq!(account.total(), dec!(100));
+
+        ledger.post(account.cancel_reversal(dec!(40)));
+        assert_eq!(account.available(), dec!(60));
+        assert_eq!(account.held(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(60));
+    }
+
+    #[test]
+    fn test_withdrawal_chargeback_credits_available_and_locks_account() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.withdraw(dec!(40)));
+        ledger.post(account.hold_reversal(dec!(40)));
+        ledger.post(account.chargeback_reversal(dec!(40), true));
+
+        assert_eq!(account.available(), dec!(100));
+        assert_eq!(account.held(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(100));
+        assert!(account.is_locked());
+    }
+
+    #[test]
+    fn test_uncleared_deposit_becomes_available_once_cleared() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit_uncleared(dec!(100)));
+
+        assert_eq!(account.available(), Decimal::ZERO);
+        assert_eq!(account.pending(), dec!(100));
+        assert_eq!(account.total(), dec!(100));
+
+        ledger.post(account.clear(dec!(100)));
+        assert_eq!(account.available(), dec!(100));
+        assert_eq!(account.pending(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(100));
+    }
+
+    #[test]
+    fn test_reserve_is_released_to_available() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(90)));
+        ledger.post(account.deposit_reserved(dec!(10)));
+
+        assert_eq!(account.available(), dec!(90));
+        assert_eq!(account.reserved(), dec!(10));
+        assert_eq!(account.total(), dec!(100));
+
+        ledger.post(account.release_reserve(dec!(10)));
+        assert_eq!(account.available(), dec!(100));
+        assert_eq!(account.reserved(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(100));
+    }
+
+    #[test]
+    fn test_reserved_funds_keep_account_non_empty() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit_reserved(dec!(10)));
+        assert!(!account.is_empty());
+
+        ledger.post(account.release_reserve(dec!(10)));
+        ledger.post(account.withdraw(dec!(10)));
+        assert!(account.is_empty());
+    }
+
+    #[test]
+    fn test_authorize_then_capture_debits_total() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.authorize(dec!(40)));
+
+        assert_eq!(account.available(), dec!(60));
+        assert_eq!(account.authorized(), dec!(40));
+        assert_eq!(account.total(), dec!(100));
+
+        ledger.post(account.capture(dec!(40)));
+        assert_eq!(account.available(), dec!(60));
+        assert_eq!(account.authorized(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(60));
+    }
+
+    #[test]
+    fn test_void_releases_authorized_funds() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.authorize(dec!(40)));
+        assert!(!account.is_empty());
+
+        ledger.post(account.void(dec!(40)));
+        assert_eq!(account.available(), dec!(100));
+        assert_eq!(account.authorized(), Decimal::ZERO);
+        assert_eq!(account.total(), dec!(100));
+    }
+
+    #[test]
+    fn test_headroom_includes_credit_limit() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        account.set_credit_limit(dec!(50));
+        assert_eq!(account.headroom(), dec!(150));
+
+        ledger.post(account.withdraw(dec!(130)));
+        assert_eq!(account.available(), dec!(-30));
+        assert_eq!(account.total(), dec!(-30));
+        assert_eq!(account.headroom(), dec!(20));
+
+        // Lowering the limit below the overdraft leaves a negative headroom
+        account.set_credit_limit(dec!(10));
+        assert_eq!(account.headroom(), dec!(-20));
+    }
+
+    #[test]
+    fn test_freeze_reasons_are_lifted_independently() {
+        let mut account = Account::new(1, Currency::default());
+        account.freeze(FreezeReason::Chargeback);
+        account.freeze(FreezeReason::Compliance);
+        assert!(account.is_locked());
+
+        account.unfreeze(FreezeReason::Chargeback);
+        assert!(account.is_locked());
+        assert_eq!(
+            account.status(),
+            AccountStatus::Frozen(BTreeSet::from([FreezeReason::Compliance]))
+        );
+
+        account.unfreeze(FreezeReason::Compliance);
+        assert!(!account.is_locked());
+        assert_eq!(account.status(), AccountStatus::Active);
+    }
+
+    #[test]
+    fn test_freeze_takes_precedence_over_restriction() {
+        let mut account = Account::new(1, Currency::default());
+        account.restrict(Restriction::WithdrawOnly);
+        assert_eq!(account.status(), AccountStatus::WithdrawOnly);
+
+        account.freeze(FreezeReason::Compliance);
+        assert!(matches!(account.status(), AccountStatus::Frozen(_)));
+
+        account.unfreeze(FreezeReason::Compliance);
+        assert_eq!(account.status(), AccountStatus::WithdrawOnly);
+    }
+
+    #[test]
+    fn test_close_locks_account() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(10)));
+        ledger.post(account.withdraw(dec!(10)));
+        assert!(account.is_empty());
+
+        account.close();
+        assert!(account.is_closed());
+        assert!(account.is_locked());
+        assert_eq!(account.status(), AccountStatus::Closed);
+    }
+
+    #[test]
+    fn test_balances_match_ledger() {
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100)));
+        ledger.post(account.deposit_uncleared(dec!(20)));
+        ledger.post(account.hold(dec!(30)));
+        ledger.post(account.authorize(dec!(10)));
+        ledger.post(account.chargeback(dec!(30), false));
+
+        for bucket in Bucket::ALL {
+            let ledger_account = LedgerAccount::Client(1, bucket);
+            assert_eq!(
+                account.balance(bucket),
+                ledger.balance(account.currency(), ledger_account)
+            );
+        }
+        assert_eq!(account.total(), dec!(90));
+        let usd = Currency::default();
+        assert_eq!(ledger.balance(usd, LedgerAccount::Settlement), dec!(-120));
+        assert_eq!(ledger.balance(usd, LedgerAccount::Chargebacks), dec!(30));
+        assert_eq!(ledger.trial_balance(usd), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_conversion_books_through_exchange() {
+        let usd = Currency::default();
+        let eur: Currency = "EUR".parse().unwrap();
+        let mut source = Account::new(1, usd);
+        let mut target = Account::new(1, eur);
+        let mut ledger = Ledger::default();
+        ledger.post(source.deposit(dec!(100)));
+        ledger.post(source.convert_out(dec!(40)));
+        ledger.post(target.convert_in(dec!(36.8)));
+
+        assert_eq!(source.available(), dec!(60));
+        assert_eq!(target.available(), dec!(36.8));
+        assert_eq!(ledger.balance(usd, LedgerAccount::Exchange), dec!(40));
+        assert_eq!(ledger.balance(eur, LedgerAccount::Exchange), dec!(-36.8));
+        assert!(ledger.is_balanced());
+    }
+
+    #[test]
+    fn test_rejects_row_with_inconsistent_total() {
+        let input = "client,currency,available,held,pending,authorized,reserved,total,headroom,\
+locked,status
+1,USD,10,0,0,0,0,15,10,false,active";
+        let mut reader = csv::Reader::from_reader(input.as_bytes());
+        let result: Result<Account, _> = reader.deserialize().next().unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_export_keeps_finer_currency_scales() {
+        let mut account = Account::new(1, "BTC".parse().unwrap());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(0.00000001)));
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(&account).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert!(output.contains("1,BTC,0.00000001,0.00000000,"));
+    }
+
+    #[test]
+    fn test_new_currency_account_inherits_status() {
+        let mut usd = Account::new(1, Currency::default());
+        usd.freeze(FreezeReason::Compliance);
+        usd.restrict(Restriction::DepositOnly);
+
+        let mut jpy = Account::new(1, "JPY".parse().unwrap());
+        jpy.inherit_status(&usd);
+        assert_eq!(jpy.status(), usd.status());
+        assert!(jpy.is_frozen_for(FreezeReason::Compliance));
+    }
+
     #[test]
     fn test_normalize_trims_trailing_zeros() {
-        let mut account = Account::new(1);
-        account.deposit(dec!(100.0000));
+        let mut account = Account::new(1, Currency::default());
+        let mut ledger = Ledger::default();
+        ledger.post(account.deposit(dec!(100.0000)));
 
         // After normalize, should be compact
         assert_eq!(account.available().to_string(), "100");
@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::account::{Account, ClientId};
+use super::currency::Currency;
+
+/// Accounts of all clients, one per client and currency.
+///
+/// A client's accounts share their status: freezes, restrictions and closure always apply to
+/// every currency of the client.
+#[derive(Debug, Default)]
+pub(super) struct Accounts {
+    clients: HashMap<ClientId, BTreeMap<Currency, Account>>,
+}
+
+impl Accounts {
+    /// Returns the account of a client in a currency
+    pub(super) fn get(&self, client_id: ClientId, currency: Currency) -> Option<&Account> {
+        self.clients.get(&client_id)?.get(&currency)
+    }
+
+    /// Returns the account of a client in a currency
+    pub(super) fn get_mut(
+        &mut self,
+        client_id: ClientId,
+        currency: Currency,
+    ) -> Option<&mut Account> {
+        self.clients.get_mut(&client_id)?.get_mut(&currency)
+    }
+
+    /// Returns the account of a client in a currency, or any other account of the client if
+    /// it has none in that currency yet. As all accounts of a client share their status, this
+    /// is the account to check before opening a new one.
+    pub(super) fn get_or_sibling(
+        &self,
+        client_id: ClientId,
+        currency: Currency,
+    ) -> Option<&Account> {
+        let accounts = self.clients.get(&client_id)?;
+        accounts.get(&currency).or_else(|| accounts.values().next())
+    }
+
+    /// Returns whether the client has an account in the currency
+    pub(super) fn contains(&self, client_id: ClientId, currency: Currency) -> bool {
+        self.get(client_id, currency).is_some()
+    }
+
+    /// Returns the account of a client in a currency, opening it with `open` if it doesn't
+    /// exist. A new account of a known client takes over the status of its other accounts.
+    pub(super) fn get_or_open(
+        &mut self,
+        client_id: ClientId,
+        currency: Currency,
+        open: impl FnOnce() -> Account,
+    ) -> &mut Account {
+        let accounts = self.clients.entry(client_id).or_default();
+        let sibling = if accounts.contains_key(&currency) {
+            None
+        } else {
+            accounts.values().next().cloned()
+        };
+        accounts.entry(currency).or_insert_with(|| {
+            let mut account = open();
+            if let Some(sibling) = sibling {
+                account.inherit_status(&sibling);
+            }
+            account
+        })
+    }
+
+    /// Returns every account of a client by currency, or `None` for unknown clients
+    pub(super) fn client_mut(
+        &mut self,
+        client_id: ClientId,
+    ) -> Option<&mut BTreeMap<Currency, Account>> {
+        self.clients.get_mut(&client_id)
+    }
+
+    /// Returns every account of a client, ordered by currency
+    pub(super) fn client(&self, client_id: ClientId) -> impl Iterator<Item = &Account> {
+        self.clients
+            .get(&client_id)
+            .into_iter()
+            .flat_map(BTreeMap::values)
+    }
+
+    /// Returns every account of every client
+    pub(super) fn iter(&self) -> impl Iterator<Item = &Account> {
+        self.clients.values().flat_map(BTreeMap::values)
+    }
+
+    /// Returns the number of accounts, counting every currency of a client
+    pub(super) fn len(&self) -> usize {
+        self.clients.values().map(BTreeMap::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::status::FreezeReason;
+
+    #[test]
+    fn test_accounts_are_kept_per_currency() {
+        let mut accounts = Accounts::default();
+        let usd = Currency::default();
+        let jpy: Currency = "JPY".parse().unwrap();
+
+        accounts.get_or_open(1, usd, || Account::new(1, usd));
+        accounts.get_or_open(1, jpy, || Account::new(1, jpy));
+        accounts.get_or_open(2, usd, || Account::new(2, usd));
+
+        assert_eq!(accounts.len(), 3);
+        assert!(accounts.contains(1, jpy));
+        assert!(!accounts.contains(2, jpy));
+        assert_eq!(
+            accounts.client_mut(1).map(|accounts| accounts.len()),
+            Some(2)
+        );
+        assert_eq!(
+            accounts
+                .client(1)
+                .map(Account::currency)
+                .collect::<Vec<_>>(),
+            vec![jpy, usd]
+        );
+        assert_eq!(accounts.client(3).count(), 0);
+    }
+
+    #[test]
+    fn test_new_currency_account_shares_client_status() {
+        let mut accounts = Accounts::default();
+        let usd = Currency::default();
+        let eur: Currency = "EUR".parse().unwrap();
+
+        accounts
+            .get_or_open(1, usd, || Account::new(1, usd))
+            .freeze(FreezeReason::Compliance);
+        assert!(accounts
+            .get_or_sibling(1, eur)
+            .is_some_and(Account::is_locked));
+
+        let account = accounts.get_or_open(1, eur, || Account::new(1, eur));
+        assert!(account.is_frozen_for(FreezeReason::Compliance));
+    }
+}
@@ -0,0 +1,148 @@
+use super::fees::FeeSchedule;
+use super::fx::RateTable;
+use super::payment_engine::PaymentEngine;
+use super::policy::{ClientConfig, EnginePolicy, ExpiryAction};
+
+/// Builder for a `PaymentEngine` with a custom `EnginePolicy`.
+///
+/// ```
+/// use payment_engine::PaymentEngine;
+///
+/// let engine = PaymentEngine::builder()
+///     .idempotent_replays(true)
+///     .lock_on_chargeback(false)
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+#[must_use]
+pub struct PaymentEngineBuilder {
+    policy: EnginePolicy,
+    rates: RateTable,
+}
+
+impl PaymentEngineBuilder {
+    /// Replace the whole policy, e.g. one loaded with `EnginePolicy::from_toml`
+    pub fn policy(mut self, policy: EnginePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// See `EnginePolicy::idempotent_replays`
+    pub fn idempotent_replays(mut self, enabled: bool) -> Self {
+        self.policy.idempotent_replays = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::allow_redispute`
+    pub fn allow_redispute(mut self, enabled: bool) -> Self {
+        self.policy.allow_redispute = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::allow_withdrawal_disputes`
+    pub fn allow_withdrawal_disputes(mut self, enabled: bool) -> Self {
+        self.policy.allow_withdrawal_disputes = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::allow_negative_available_on_dispute`
+    pub fn allow_negative_available_on_dispute(mut self, enabled: bool) -> Self {
+        self.policy.allow_negative_available_on_dispute = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::require_account_for_withdrawal`
+    pub fn require_account_for_withdrawal(mut self, enabled: bool) -> Self {
+        self.policy.require_account_for_withdrawal = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::lock_on_chargeback`
+    pub fn lock_on_chargeback(mut self, enabled: bool) -> Self {
+        self.policy.lock_on_chargeback = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::reject_deposits_on_locked`
+    pub fn reject_deposits_on_locked(mut self, enabled: bool) -> Self {
+        self.policy.reject_deposits_on_locked = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::allow_adjustments_on_restricted`
+    pub fn allow_adjustments_on_restricted(mut self, enabled: bool) -> Self {
+        self.policy.allow_adjustments_on_restricted = enabled;
+        self
+    }
+
+    /// See `EnginePolicy::deposit_clearing_rows`
+    pub fn deposit_clearing_rows(mut self, rows: u64) -> Self {
+        self.policy.deposit_clearing_rows = rows;
+        self
+    }
+
+    /// See `EnginePolicy::deposit_clearing_days`
+    pub fn deposit_clearing_days(mut self, days: u64) -> Self {
+        self.policy.deposit_clearing_days = days;
+        self
+    }
+
+    /// See `EnginePolicy::dispute_expiry_rows`
+    pub fn dispute_expiry_rows(mut self, rows: u64) -> Self {
+        self.policy.dispute_expiry_rows = rows;
+        self
+    }
+
+    /// See `EnginePolicy::dispute_expiry_days`
+    pub fn dispute_expiry_days(mut self, days: u64) -> Self {
+        self.policy.dispute_expiry_days = days;
+        self
+    }
+
+    /// See `EnginePolicy::dispute_window_days`
+    pub fn dispute_window_days(mut self, days: u64) -> Self {
+        self.policy.dispute_window_days = days;
+        self
+    }
+
+    /// See `EnginePolicy::reorder_window_rows`
+    pub fn reorder_window_rows(mut self, rows: u64) -> Self {
+        self.policy.reorder_window_rows = rows;
+        self
+    }
+
+    /// See `EnginePolicy::reorder_buffer_size`
+    pub fn reorder_buffer_size(mut self, size: usize) -> Self {
+        self.policy.reorder_buffer_size = size;
+        self
+    }
+
+    /// See `EnginePolicy::dispute_expiry_action`
+    pub fn dispute_expiry_action(mut self, action: ExpiryAction) -> Self {
+        self.policy.dispute_expiry_action = action;
+        self
+    }
+
+    /// Add the settings of a single client, see `EnginePolicy::clients`
+    pub fn client(mut self, config: ClientConfig) -> Self {
+        self.policy.clients.push(config);
+        self
+    }
+
+    /// See `EnginePolicy::fees`
+    pub fn fees(mut self, fees: FeeSchedule) -> Self {
+        self.policy.fees = fees;
+        self
+    }
+
+    /// Exchange rates of `convert` rows, e.g. loaded with `RateTable::from_csv`
+    pub fn rates(mut self, rates: RateTable) -> Self {
+        self.rates = rates;
+        self
+    }
+
+    /// Build the `PaymentEngine`
+    pub fn build(self) -> PaymentEngine {
+        PaymentEngine::with_policy(self.policy).with_rates(self.rates)
+    }
+}
@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Currency code of an account and the transactions booked on it, such as `USD`, `JPY` or
+/// `BTC`.
+///
+/// Codes are 1 to 8 ASCII letters or digits and are stored uppercase, so `usd` and `USD` are
+/// the same currency. Rows without a `currency` column are booked in `Currency::default()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Currency([u8; Currency::MAX_LEN]);
+
+impl Currency {
+    /// Longest supported currency code
+    pub const MAX_LEN: usize = 8;
+
+    /// Decimal places of currencies without a dedicated scale
+    pub const DEFAULT_SCALE: u32 = 4;
+
+    /// Decimal places of the finest supported currency
+    pub const MAX_SCALE: u32 = 8;
+
+    /// Returns the currency code
+    pub fn code(&self) -> &str {
+        let len = self.0.iter().position(|b| *b == 0).unwrap_or(Self::MAX_LEN);
+        // Only ASCII alphanumerics are ever stored
+        std::str::from_utf8(&self.0[..len]).unwrap_or_default()
+    }
+
+    /// Returns the number of decimal places amounts in this currency may have
+    pub fn scale(&self) -> u32 {
+        match self.code() {
+            "JPY" | "KRW" | "VND" | "CLP" | "ISK" => 0,
+            "BHD" | "JOD" | "KWD" | "OMR" | "TND" => 3,
+            "BTC" => 8,
+            _ => Self::DEFAULT_SCALE,
+        }
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self(*b"USD\0\0\0\0\0")
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > Self::MAX_LEN || !s.bytes().all(|b| b.is_ascii_alphanumeric())
+        {
+            return Err(format!("invalid currency code: {s}"));
+        }
+        let mut code = [0; Self::MAX_LEN];
+        for (byte, c) in code.iter_mut().zip(s.bytes()) {
+            *byte = c.to_ascii_uppercase();
+        }
+        Ok(Self(code))
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        currency.code().to_string()
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uppercases_code() {
+        let currency: Currency = "btc".parse().unwrap();
+        assert_eq!(currency.code(), "BTC");
+        assert_eq!(currency, "BTC".parse().unwrap());
+    }
+
+    #[test]
+    fn test_default_is_usd() {
+        assert_eq!(Currency::default().to_string(), "USD");
+        assert_eq!(Currency::default().scale(), 4);
+    }
+
+    #[test]
+    fn test_currency_specific_scales() {
+        let scale = |code: &str| code.parse::<Currency>().unwrap().scale();
+        assert_eq!(scale("JPY"), 0);
+        assert_eq!(scale("KWD"), 3);
+        assert_eq!(scale("EUR"), 4);
+        assert_eq!(scale("BTC"), 8);
+    }
+
+    #[test]
+    fn test_rejects_invalid_codes() {
+        for code in ["", "US D", "EURO-USD", "TOOLONGCODE", "€"] {
+            assert!(code.parse::<Currency>().is_err(), "{code}");
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use crate::engine::currency::Currency;
+use crate::engine::lifecycle::AuthorizationState;
+use crate::engine::status::{AccountStatus, FreezeReason};
 use crate::engine::transaction::TransactionRecord;
 use crate::engine::Decimal;
 
@@ -11,6 +14,14 @@ pub enum Error {
     Csv(#[from] csv::Error),
     #[error("Transaction error: {0}")]
     Transaction(#[from] TransactionError),
+    #[error("Policy error: {0}")]
+    Policy(#[from] toml::de::Error),
+    #[error("Invalid policy: {0}")]
+    InvalidPolicy(String),
+    #[error("Ledger is unbalanced: {0}")]
+    Unbalanced(String),
+    #[error("Invalid exchange rates: {0}")]
+    InvalidRates(String),
 }
 
 /// Errors during `TransactionRecord` -> `Transaction` conversion (hard errors).
@@ -27,25 +38,162 @@ pub enum ProcessingError {
     #[error("Transaction {tx} not found")]
     TransactionNotFound { tx: u32 },
 
+    #[error("Transaction {tx} can't be referenced by this row")]
+    IneligibleTransaction { tx: u32 },
+
     #[error("Client mismatch: transaction {tx} belongs to client {expected}, not {got}")]
     ClientMismatch { tx: u32, expected: u16, got: u16 },
 
+    #[error("Currency mismatch: transaction {tx} is in {expected}, not {got}")]
+    CurrencyMismatch {
+        tx: u32,
+        expected: Currency,
+        got: Currency,
+    },
+
+    #[error(
+        "Amount {amount} referencing transaction {tx} has more decimal places than {currency} \
+         allows"
+    )]
+    AmountTooPrecise {
+        tx: u32,
+        amount: Decimal,
+        currency: Currency,
+    },
+
     #[error("Transaction {tx} is not under dispute")]
     NotUnderDispute { tx: u32 },
 
     #[error("Transaction {tx} is already under dispute")]
     AlreadyUnderDispute { tx: u32 },
 
-    #[error("Insufficient funds: client {client} has {available}, requested {requested}")]
+    #[error("Transaction {tx} has already been charged back")]
+    AlreadyChargedBack { tx: u32 },
+
+    #[error(
+        "Transaction {tx} can no longer be disputed, its {days} day dispute window has passed"
+    )]
+    DisputeWindowClosed { tx: u32, days: u64 },
+
+    #[error("Transaction {tx} was already disputed and resolved, re-disputes are not allowed")]
+    RedisputeNotAllowed { tx: u32 },
+
+    #[error("Dispute exceeds transaction {tx}: requested {requested}, undisputed {remaining}")]
+    ExceedsUndisputedAmount {
+        tx: u32,
+        requested: Decimal,
+        remaining: Decimal,
+    },
+
+    #[error(
+        "Settlement exceeds disputed amount of transaction {tx}: requested {requested}, \
+         disputed {disputed}"
+    )]
+    ExceedsDisputedAmount {
+        tx: u32,
+        requested: Decimal,
+        disputed: Decimal,
+    },
+
+    #[error("Transaction {tx} has already been refunded")]
+    AlreadyRefunded { tx: u32 },
+
+    #[error("Refund exceeds transaction {tx}: requested {requested}, refundable {refundable}")]
+    ExceedsRefundableAmount {
+        tx: u32,
+        requested: Decimal,
+        refundable: Decimal,
+    },
+
+    #[error("Authorization {tx} is no longer pending, it was {state}")]
+    AuthorizationNotPending { tx: u32, state: AuthorizationState },
+
+    #[error(
+        "Insufficient funds: client {client} has {available} with credit limit {limit}, \
+         requested {requested}"
+    )]
     InsufficientFunds {
         client: u16,
         available: Decimal,
+        limit: Decimal,
         requested: Decimal,
     },
 
+    #[error("Fee {fee} of deposit {tx} leaves nothing of its amount {amount}")]
+    FeeExceedsAmount {
+        tx: u32,
+        amount: Decimal,
+        fee: Decimal,
+    },
+
     #[error("Account {client} not found")]
     AccountNotFound { client: u16 },
 
     #[error("Account {client} is locked")]
     AccountLocked { client: u16 },
+
+    #[error("Account {client} is not frozen for {reason}")]
+    AccountNotFrozen { client: u16, reason: FreezeReason },
+
+    #[error("Account {client} is restricted: {status}")]
+    AccountRestricted { client: u16, status: AccountStatus },
+
+    #[error("Account {client} is closed")]
+    AccountClosed { client: u16 },
+
+    #[error("Account {client} still holds funds: available {available}, held {held}")]
+    AccountNotEmpty {
+        client: u16,
+        available: Decimal,
+        held: Decimal,
+    },
+
+    #[error("Transaction {tx} has already been applied")]
+    DuplicateTransaction { tx: u32 },
+
+    #[error("No exchange rate from {from} to {to}")]
+    RateNotFound { from: Currency, to: Currency },
+
+    #[error("Conversion {tx} of {amount} {from} is worth nothing in {to}")]
+    ConversionTooSmall {
+        tx: u32,
+        amount: Decimal,
+        from: Currency,
+        to: Currency,
+    },
+}
+
+impl ProcessingError {
+    /// Returns the kind of the error in `snake_case`, e.g. `insufficient_funds`, without the
+    /// details of the failed row
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProcessingError::TransactionNotFound { .. } => "transaction_not_found",
+            ProcessingError::IneligibleTransaction { .. } => "ineligible_transaction",
+            ProcessingError::ClientMismatch { .. } => "client_mismatch",
+            ProcessingError::CurrencyMismatch { .. } => "currency_mismatch",
+            ProcessingError::AmountTooPrecise { .. } => "amount_too_precise",
+            ProcessingError::NotUnderDispute { .. } => "not_under_dispute",
+            ProcessingError::AlreadyUnderDispute { .. } => "already_under_dispute",
+            ProcessingError::AlreadyChargedBack { .. } => "already_charged_back",
+            ProcessingError::DisputeWindowClosed { .. } => "dispute_window_closed",
+            ProcessingError::RedisputeNotAllowed { .. } => "redispute_not_allowed",
+            ProcessingError::ExceedsUndisputedAmount { .. } => "exceeds_undisputed_amount",
+            ProcessingError::ExceedsDisputedAmount { .. } => "exceeds_disputed_amount",
+            ProcessingError::AlreadyRefunded { .. } => "already_refunded",
+            ProcessingError::ExceedsRefundableAmount { .. } => "exceeds_refundable_amount",
+            ProcessingError::AuthorizationNotPending { .. } => "authorization_not_pending",
+            ProcessingError::InsufficientFunds { .. } => "insufficient_funds",
+            ProcessingError::FeeExceedsAmount { .. } => "fee_exceeds_amount",
+            ProcessingError::AccountNotFound { .. } => "account_not_found",
+            ProcessingError::AccountLocked { .. } => "account_locked",
+            ProcessingError::AccountNotFrozen { .. } => "account_not_frozen",
+            ProcessingError::AccountRestricted { .. } => "account_restricted",
+            ProcessingError::AccountClosed { .. } => "account_closed",
+            ProcessingError::AccountNotEmpty { .. } => "account_not_empty",
+            ProcessingError::DuplicateTransaction { .. } => "duplicate_transaction",
+            ProcessingError::RateNotFound { .. } => "rate_not_found",
+            ProcessingError::ConversionTooSmall { .. } => "conversion_too_small",
+        }
+    }
 }
@@ -0,0 +1,296 @@
+use serde::Deserialize;
+
+use super::account::ClientId;
+use super::currency::Currency;
+use super::Decimal;
+
+/// Fees charged on applied deposits and withdrawals, configured in the policy's `[fees]` table.
+/// Flat fees and bounds are in the currency of the transaction.
+///
+/// ```toml
+/// [fees]
+/// house_account = 9999
+///
+/// [fees.withdrawal]
+/// flat = 0.5
+/// percentage = 1
+/// max = 25
+///
+/// [[fees.withdrawal.tiers]]
+/// from = 1000
+/// percentage = 0.5
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeSchedule {
+    /// Client whose account collects the fees as revenue, required once any fee is configured.
+    /// Transactions of the house account itself are never charged.
+    pub house_account: Option<ClientId>,
+    /// Fee deducted from every deposit before it is credited.
+    pub deposit: Option<Fee>,
+    /// Fee debited on top of every withdrawal.
+    pub withdrawal: Option<Fee>,
+}
+
+/// Fee of a single transaction type.
+///
+/// The fee is `flat + amount * percentage / 100` of the highest tier the amount reaches,
+/// or of the base rates below the first tier, kept within `min` and `max`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fee {
+    /// Fixed part of the fee.
+    pub flat: Decimal,
+    /// Percentage (0 to 100) of the transaction amount.
+    pub percentage: Decimal,
+    /// Lower bound of the fee.
+    pub min: Decimal,
+    /// Upper bound of the fee, unbounded if not set.
+    pub max: Option<Decimal>,
+    /// Rates replacing the base rates for larger amounts.
+    pub tiers: Vec<FeeTier>,
+}
+
+/// Rates that apply to amounts of at least `from`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeTier {
+    /// Smallest amount the tier applies to.
+    pub from: Decimal,
+    /// Fixed part of the fee.
+    pub flat: Decimal,
+    /// Percentage (0 to 100) of the transaction amount.
+    pub percentage: Decimal,
+}
+
+impl FeeSchedule {
+    /// Returns the fee the client pays for depositing `amount`
+    pub(crate) fn deposit_fee(
+        &self,
+        client_id: ClientId,
+        currency: Currency,
+        amount: Decimal,
+    ) -> Decimal {
+        self.fee(self.deposit.as_ref(), client_id, currency, amount)
+    }
+
+    /// Returns the fee the client pays for withdrawing `amount`
+    pub(crate) fn withdrawal_fee(
+        &self,
+        client_id: ClientId,
+        currency: Currency,
+        amount: Decimal,
+    ) -> Decimal {
+        self.fee(self.withdrawal.as_ref(), client_id, currency, amount)
+    }
+
+    fn fee(
+        &self,
+        fee: Option<&Fee>,
+        client_id: ClientId,
+        currency: Currency,
+        amount: Decimal,
+    ) -> Decimal {
+        match (fee, self.house_account) {
+            (Some(fee), Some(house)) if house != client_id => fee.charge(amount, currency),
+            _ => Decimal::ZERO,
+        }
+    }
+
+    /// Check the values that can't be expressed by the field types alone.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let fees = [("deposit", &self.deposit), ("withdrawal", &self.withdrawal)];
+        for (name, fee) in fees {
+            let Some(fee) = fee else { continue };
+            if self.house_account.is_none() {
+                return Err(format!("{name} fee requires a house account"));
+            }
+            fee.validate()
+                .map_err(|reason| format!("{name} fee: {reason}"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Fee {
+    /// Returns the fee for a transaction of `amount`, rounded to the scale of its `currency`
+    pub fn charge(&self, amount: Decimal, currency: Currency) -> Decimal {
+        let (flat, percentage) = self
+            .tiers
+            .iter()
+            .filter(|tier| tier.from <= amount)
+            .max_by_key(|tier| tier.from)
+            .map_or((self.flat, self.percentage), |tier| {
+                (tier.flat, tier.percentage)
+            });
+
+        let fee = (flat + amount * percentage / Decimal::ONE_HUNDRED).max(self.min);
+        let fee = self.max.map_or(fee, |max| fee.min(max));
+        fee.round_dp(currency.scale()).normalize()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let rates = std::iter::once((self.flat, self.percentage))
+            .chain(self.tiers.iter().map(|tier| (tier.flat, tier.percentage)));
+        for (flat, percentage) in rates {
+            if flat.is_sign_negative() {
+                return Err(format!("flat fee must not be negative, got {flat}"));
+            }
+            if !(Decimal::ZERO..=Decimal::ONE_HUNDRED).contains(&percentage) {
+                return Err(format!(
+                    "percentage must be between 0 and 100, got {percentage}"
+                ));
+            }
+        }
+        if self.min.is_sign_negative() {
+            return Err(format!("minimum must not be negative, got {}", self.min));
+        }
+        if let Some(max) = self.max.filter(|max| *max < self.min) {
+            return Err(format!(
+                "maximum {max} must not be below minimum {}",
+                self.min
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn usd() -> Currency {
+        Currency::default()
+    }
+
+    fn tiered_fee() -> Fee {
+        Fee {
+            flat: dec!(1),
+            percentage: dec!(2),
+            min: dec!(1.5),
+            max: Some(dec!(30)),
+            tiers: vec![
+                FeeTier {
+                    from: dec!(1000),
+                    flat: dec!(0),
+                    percentage: dec!(1),
+                },
+                FeeTier {
+                    from: dec!(500),
+                    flat: dec!(0.5),
+                    percentage: dec!(1.5),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_flat_and_percentage_fee() {
+        let fee = Fee {
+            flat: dec!(0.25),
+            percentage: dec!(1),
+            ..Fee::default()
+        };
+        assert_eq!(fee.charge(dec!(100), usd()), dec!(1.25));
+    }
+
+    #[test]
+    fn test_highest_reached_tier_applies() {
+        let fee = tiered_fee();
+        assert_eq!(fee.charge(dec!(100), usd()), dec!(3));
+        assert_eq!(fee.charge(dec!(500), usd()), dec!(8));
+        assert_eq!(fee.charge(dec!(1000), usd()), dec!(10));
+    }
+
+    #[test]
+    fn test_fee_is_capped() {
+        let fee = tiered_fee();
+        assert_eq!(fee.charge(dec!(10), usd()), dec!(1.5));
+        assert_eq!(fee.charge(dec!(5000), usd()), dec!(30));
+    }
+
+    #[test]
+    fn test_fee_is_rounded_to_4_places() {
+        let fee = Fee {
+            percentage: dec!(0.333),
+            ..Fee::default()
+        };
+        assert_eq!(fee.charge(dec!(1.2345), usd()), dec!(0.0041));
+    }
+
+    #[test]
+    fn test_house_account_is_not_charged() {
+        let usd = Currency::default();
+        let schedule = FeeSchedule {
+            house_account: Some(9),
+            deposit: Some(tiered_fee()),
+            withdrawal: None,
+        };
+        assert_eq!(schedule.deposit_fee(1, usd, dec!(100)), dec!(3));
+        assert_eq!(schedule.deposit_fee(9, usd, dec!(100)), dec!(0));
+        assert_eq!(schedule.withdrawal_fee(1, usd, dec!(100)), dec!(0));
+    }
+
+    #[test]
+    fn test_other_currencies_are_charged() {
+        let schedule = FeeSchedule {
+            house_account: Some(9),
+            deposit: Some(tiered_fee()),
+            withdrawal: None,
+        };
+        assert_eq!(
+            schedule.deposit_fee(1, "EUR".parse().unwrap(), dec!(100)),
+            dec!(3)
+        );
+    }
+
+    #[test]
+    fn test_fee_is_rounded_to_the_currency_scale() {
+        let fee = Fee {
+            percentage: dec!(0.333),
+            ..Fee::default()
+        };
+        assert_eq!(fee.charge(dec!(1500), "JPY".parse().unwrap()), dec!(5));
+        assert_eq!(
+            fee.charge(dec!(0.01234567), "BTC".parse().unwrap()),
+            dec!(0.00004111)
+        );
+    }
+
+    #[test]
+    fn test_validate_requires_house_account() {
+        let schedule = FeeSchedule {
+            deposit: Some(tiered_fee()),
+            ..FeeSchedule::default()
+        };
+        assert!(schedule.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_rates() {
+        let invalid = [
+            Fee {
+                percentage: dec!(101),
+                ..Fee::default()
+            },
+            Fee {
+                flat: dec!(-1),
+                ..Fee::default()
+            },
+            Fee {
+                min: dec!(5),
+                max: Some(dec!(1)),
+                ..Fee::default()
+            },
+        ];
+        for fee in invalid {
+            let schedule = FeeSchedule {
+                house_account: Some(9),
+                withdrawal: Some(fee),
+                ..FeeSchedule::default()
+            };
+            assert!(schedule.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Deserializer};
+
+use super::account::ClientId;
+use super::currency::Currency;
+use super::error::Error;
+use super::timestamp::Timestamp;
+use super::Decimal;
+
+/// Exchange rate from `base` to `quote`, effective from a point in time or a row on.
+///
+/// Rate tables are loaded from CSV files with one rate per line:
+///
+/// ```csv
+/// base,quote,rate,spread,from,from_row
+/// USD,EUR,0.92,0.5,,
+/// USD,EUR,0.93,0.5,2024-06-01T00:00:00Z,
+/// EUR,USD,1.08,0.5,,
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExchangeRate {
+    /// Currency that is sold
+    pub base: Currency,
+    /// Currency that is bought
+    pub quote: Currency,
+    /// Units of `quote` per unit of `base`
+    pub rate: Decimal,
+    /// Percentage (0 to 100) of the converted amount kept as the operator's margin
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub spread: Decimal,
+    /// When the rate takes effect for rows with a timestamp, it replaces earlier rates of the
+    /// pair from then on. Empty applies by `from_row` instead.
+    #[serde(default)]
+    pub from: Option<Timestamp>,
+    /// First row the rate applies to when it has no `from` timestamp, or the row has none.
+    /// Empty or `0` applies from the start.
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub from_row: u64,
+}
+
+/// Deserialize an empty CSV field as the default value of its type
+fn empty_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl ExchangeRate {
+    /// Returns the amount of `quote` bought with `amount` of `base` after the spread,
+    /// rounded down to the decimal places of `quote`
+    pub fn convert(&self, amount: Decimal) -> Decimal {
+        let gross = amount * self.rate;
+        let spread = gross * self.spread / Decimal::ONE_HUNDRED;
+        (gross - spread)
+            .round_dp_with_strategy(self.quote.scale(), RoundingStrategy::ToZero)
+            .normalize()
+    }
+
+    /// Check the values that can't be expressed by the field types alone.
+    fn validate(&self) -> Result<(), String> {
+        if self.base == self.quote {
+            return Err(format!(
+                "rate must exchange two currencies, got {}",
+                self.base
+            ));
+        }
+        if self.rate <= Decimal::ZERO {
+            return Err(format!(
+                "rate of {}/{} must be positive, got {}",
+                self.base, self.quote, self.rate
+            ));
+        }
+        if !(Decimal::ZERO..Decimal::ONE_HUNDRED).contains(&self.spread) {
+            return Err(format!(
+                "spread of {}/{} must be at least 0 and below 100, got {}",
+                self.base, self.quote, self.spread
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Effective-dated exchange rates used by `convert` rows.
+///
+/// Rates are quoted per direction, a `USD`/`EUR` rate doesn't convert `EUR` into `USD`.
+/// Rates with a `from` timestamp take effect by the timestamp of the row, the others by row
+/// number like the other periods of the engine, so replaying the same input with the same table
+/// always books the same rates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateTable {
+    /// Rates effective from a point in time
+    dated: HashMap<(Currency, Currency), BTreeMap<Timestamp, ExchangeRate>>,
+    /// Rates effective from a row on
+    rates: HashMap<(Currency, Currency), BTreeMap<u64, ExchangeRate>>,
+}
+
+impl RateTable {
+    /// Load a rate table from CSV with the columns `base, quote, rate, spread, from, from_row`
+    pub fn from_csv<R: Read>(reader: R) -> Result<Self, Error> {
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let mut table = Self::default();
+        for result in csv_reader.deserialize() {
+            table.insert(result?)?;
+        }
+        Ok(table)
+    }
+
+    /// Add a rate, replacing a rate of the same pair that takes effect at the same time or row
+    pub fn insert(&mut self, rate: ExchangeRate) -> Result<(), Error> {
+        rate.validate().map_err(Error::InvalidRates)?;
+        let pair = (rate.base, rate.quote);
+        match rate.from {
+            Some(from) => {
+                self.dated.entry(pair).or_default().insert(from, rate);
+            }
+            None => {
+                self.rates
+                    .entry(pair)
+                    .or_default()
+                    .insert(rate.from_row, rate);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the rate from `base` to `quote` in effect for a row at `timestamp` and `row`.
+    ///
+    /// Rows with a timestamp take the latest rate that took effect by then. Rows without one,
+    /// or from before every dated rate of the pair, take the rate in effect at their row.
+    pub fn rate_at(
+        &self,
+        base: Currency,
+        quote: Currency,
+        timestamp: Option<Timestamp>,
+        row: u64,
+    ) -> Option<&ExchangeRate> {
+        let pair = (base, quote);
+        timestamp
+            .and_then(|timestamp| {
+                self.dated
+                    .get(&pair)?
+                    .range(..=timestamp)
+                    .next_back()
+                    .map(|(_, rate)| rate)
+            })
+            .or_else(|| {
+                self.rates
+                    .get(&pair)?
+                    .range(..=row)
+                    .next_back()
+                    .map(|(_, rate)| rate)
+            })
+    }
+
+    /// Returns whether the table holds no rates
+    pub fn is_empty(&self) -> bool {
+        self.dated.is_empty() && self.rates.is_empty()
+    }
+}
+
+/// An applied conversion with the rate and spread it was booked at, kept for audits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversion {
+    client_id: ClientId,
+    /// Row the conversion was applied at, which selected the rate
+    row: u64,
+    amount: Decimal,
+    rate: ExchangeRate,
+    converted: Decimal,
+}
+
+impl Conversion {
+    pub(super) fn new(
+        client_id: ClientId,
+        row: u64,
+        amount: Decimal,
+        rate: ExchangeRate,
+        converted: Decimal,
+    ) -> Self {
+        Self {
+            client_id,
+            row,
+            amount,
+            rate,
+            converted,
+        }
+    }
+
+    /// Returns the converting client
+    pub fn client_id(&self) -> ClientId {
+        self.client_id
+    }
+
+    /// Returns the row the conversion was applied at
+    pub fn row(&self) -> u64 {
+        self.row
+    }
+
+    /// Returns the amount debited in the base currency
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    /// Returns the amount credited in the quote currency
+    pub fn converted(&self) -> Decimal {
+        self.converted
+    }
+
+    /// Returns the applied rate, including its spread
+    pub fn rate(&self) -> &ExchangeRate {
+        &self.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn currency(code: &str) -> Currency {
+        code.parse().unwrap()
+    }
+
+    #[test]
+    fn test_convert_deducts_spread_and_rounds_down() {
+        let rate = ExchangeRate {
+            base: currency("USD"),
+            quote: currency("JPY"),
+            rate: dec!(151.37),
+            spread: dec!(1),
+            from: None,
+            from_row: 0,
+        };
+        // 10 * 151.37 = 1513.7, minus 1% = 1498.563
+        assert_eq!(rate.convert(dec!(10)), dec!(1498));
+    }
+
+    #[test]
+    fn test_rate_in_effect_at_row() {
+        let input = "base,quote,rate,spread,from_row
+USD,EUR,0.92,,
+USD,EUR,0.95,0.5,10";
+        let table = RateTable::from_csv(input.as_bytes()).unwrap();
+        let (usd, eur) = (currency("USD"), currency("EUR"));
+
+        assert_eq!(table.rate_at(usd, eur, None, 1).unwrap().rate, dec!(0.92));
+        assert_eq!(table.rate_at(usd, eur, None, 9).unwrap().rate, dec!(0.92));
+        assert_eq!(table.rate_at(usd, eur, None, 10).unwrap().rate, dec!(0.95));
+        assert!(table.rate_at(eur, usd, None, 10).is_none());
+    }
+
+    #[test]
+    fn test_rate_in_effect_at_timestamp() {
+        let input = "base,quote,rate,spread,from,from_row
+USD,EUR,0.92,,,
+USD,EUR,0.95,,2024-06-01T00:00:00Z,
+USD,EUR,0.97,,2024-07-01T00:00:00Z,";
+        let table = RateTable::from_csv(input.as_bytes()).unwrap();
+        let (usd, eur) = (currency("USD"), currency("EUR"));
+        let at = |timestamp: &str| Some(timestamp.parse::<Timestamp>().unwrap());
+
+        // The row position doesn't matter once the row has a timestamp
+        let rate_at = |timestamp, row| table.rate_at(usd, eur, timestamp, row).unwrap().rate;
+        assert_eq!(rate_at(at("2024-06-15T00:00:00Z"), 1), dec!(0.95));
+        assert_eq!(rate_at(at("2024-07-01T00:00:00Z"), 1), dec!(0.97));
+        assert_eq!(rate_at(at("2024-05-01T00:00:00Z"), 1000), dec!(0.92));
+        assert_eq!(rate_at(None, 1000), dec!(0.92));
+    }
+
+    #[test]
+    fn test_rates_are_not_effective_before_their_row() {
+        let input = "base,quote,rate,spread,from_row\nUSD,EUR,0.92,,5";
+        let table = RateTable::from_csv(input.as_bytes()).unwrap();
+        assert!(table
+            .rate_at(currency("USD"), currency("EUR"), None, 4)
+            .is_none());
+    }
+
+    #[test]
+    fn test_rejects_invalid_rates() {
+        for line in [
+            "USD,EUR,0,,",
+            "USD,EUR,-1,,",
+            "USD,EUR,0.92,100,",
+            "USD,EUR,0.92,-1,",
+            "USD,USD,1,,",
+        ] {
+            let input = format!("base,quote,rate,spread,from_row\n{line}");
+            assert!(RateTable::from_csv(input.as_bytes()).is_err(), "{line}");
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::account::ClientId;
+use super::currency::Currency;
+use super::Decimal;
+
+/// Balance bucket of a client account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bucket {
+    Available,
+    Held,
+    /// Deposits that have not cleared yet
+    Pending,
+    /// Authorized withdrawals
+    Authorized,
+    /// Rolling reserve
+    Reserved,
+}
+
+impl Bucket {
+    /// Every bucket of a client account
+    pub const ALL: [Bucket; 5] = [
+        Bucket::Available,
+        Bucket::Held,
+        Bucket::Pending,
+        Bucket::Authorized,
+        Bucket::Reserved,
+    ];
+}
+
+/// An account of the general ledger.
+///
+/// Client buckets hold the funds owed to clients, system accounts are the counterparts
+/// funds come from and go to. Every ledger account is kept separately per currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LedgerAccount {
+    /// A balance bucket of a client
+    Client(ClientId, Bucket),
+    /// Funds moving in and out of the engine: deposits, withdrawals, captures and refunds
+    Settlement,
+    /// Funds returned through chargebacks, and provisional credits of disputed withdrawals
+    Chargebacks,
+    /// Operator adjustments
+    Adjustments,
+    /// Funds exchanged between currencies, one balance per currency of the conversions
+    Exchange,
+}
+
+/// A single side of a journal entry. Positive amounts increase the account, negative amounts
+/// decrease it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Posting {
+    account: LedgerAccount,
+    amount: Decimal,
+}
+
+impl Posting {
+    pub fn account(&self) -> LedgerAccount {
+        self.account
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+}
+
+/// A balanced journal entry, moving an amount of one currency from one ledger account to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use = "journal entries must be posted to the ledger"]
+pub struct JournalEntry {
+    currency: Currency,
+    postings: [Posting; 2],
+}
+
+impl JournalEntry {
+    /// Create an entry that moves `amount` from `from` to `to`
+    pub(super) fn transfer(
+        currency: Currency,
+        from: LedgerAccount,
+        to: LedgerAccount,
+        amount: Decimal,
+    ) -> Self {
+        Self {
+            currency,
+            postings: [
+                Posting {
+                    account: from,
+                    amount: -amount,
+                },
+                Posting {
+                    account: to,
+                    amount,
+                },
+            ],
+        }
+    }
+
+    /// Returns the currency of the entry
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Returns the postings of the entry
+    pub fn postings(&self) -> &[Posting] {
+        &self.postings
+    }
+
+    /// Returns whether the postings of the entry sum to zero
+    pub fn is_balanced(&self) -> bool {
+        self.postings
+            .iter()
+            .map(Posting::amount)
+            .sum::<Decimal>()
+            .is_zero()
+    }
+}
+
+/// Double-entry general ledger behind the client account balances.
+///
+/// Every balance change of the engine is posted as a `JournalEntry`, so the balances of all
+/// ledger accounts of a currency always sum to zero.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    balances: HashMap<(Currency, LedgerAccount), Decimal>,
+    entries: u64,
+}
+
+impl Ledger {
+    /// Post a journal entry to the ledger.
+    ///
+    /// # Panics (debug only)
+    /// Panics if the entry is not balanced.
+    pub(super) fn post(&mut self, entry: JournalEntry) {
+        debug_assert!(entry.is_balanced(), "unbalanced journal entry {entry:?}");
+        for posting in entry.postings() {
+            let balance = self
+                .balances
+                .entry((entry.currency, posting.account))
+                .or_default();
+            *balance = (*balance + posting.amount).normalize();
+        }
+        self.entries += 1;
+    }
+
+    /// Returns the balance of a ledger account in the given currency
+    pub fn balance(&self, currency: Currency, account: LedgerAccount) -> Decimal {
+        self.balances
+            .get(&(currency, account))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the sum of all ledger account balances in the given currency,
+    /// which is zero for a consistent ledger
+    pub fn trial_balance(&self, currency: Currency) -> Decimal {
+        self.balances
+            .iter()
+            .filter(|((account_currency, _), _)| *account_currency == currency)
+            .map(|(_, balance)| balance)
+            .sum()
+    }
+
+    /// Returns the trial balance of every currency that was posted to
+    pub fn trial_balances(&self) -> BTreeMap<Currency, Decimal> {
+        let mut sums = BTreeMap::new();
+        for ((currency, _), balance) in &self.balances {
+            *sums.entry(*currency).or_default() += balance;
+        }
+        sums
+    }
+
+    /// Returns the non-zero client bucket balances as `(currency, client, bucket, balance)`
+    pub(super) fn client_balances(
+        &self,
+    ) -> impl Iterator<Item = (Currency, ClientId, Bucket, Decimal)> + '_ {
+        self.balances
+            .iter()
+            .filter(|(_, balance)| !balance.is_zero())
+            .filter_map(|((currency, account), balance)| match account {
+                LedgerAccount::Client(client_id, bucket) => {
+                    Some((*currency, *client_id, *bucket, *balance))
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns whether the trial balance of every currency is zero
+    pub fn is_balanced(&self) -> bool {
+        self.trial_balances().values().all(Decimal::is_zero)
+    }
+
+    /// Returns the number of posted journal entries
+    pub fn entry_count(&self) -> u64 {
+        self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_entry_is_balanced() {
+        let entry = JournalEntry::transfer(
+            Currency::default(),
+            LedgerAccount::Settlement,
+            LedgerAccount::Client(1, Bucket::Available),
+            dec!(100),
+        );
+        assert!(entry.is_balanced());
+        assert_eq!(entry.postings()[0].amount(), dec!(-100));
+        assert_eq!(entry.postings()[1].amount(), dec!(100));
+    }
+
+    #[test]
+    fn test_posting_updates_balances() {
+        let usd = Currency::default();
+        let mut ledger = Ledger::default();
+        let available = LedgerAccount::Client(1, Bucket::Available);
+        let held = LedgerAccount::Client(1, Bucket::Held);
+
+        ledger.post(JournalEntry::transfer(
+            usd,
+            LedgerAccount::Settlement,
+            available,
+            dec!(100),
+        ));
+        ledger.post(JournalEntry::transfer(usd, available, held, dec!(30)));
+
+        assert_eq!(ledger.balance(usd, available), dec!(70));
+        assert_eq!(ledger.balance(usd, held), dec!(30));
+        assert_eq!(ledger.balance(usd, LedgerAccount::Settlement), dec!(-100));
+        assert_eq!(ledger.trial_balance(usd), dec!(0));
+        assert_eq!(ledger.entry_count(), 2);
+    }
+
+    #[test]
+    fn test_unknown_account_has_zero_balance() {
+        let ledger = Ledger::default();
+        assert_eq!(
+            ledger.balance(Currency::default(), LedgerAccount::Chargebacks),
+            dec!(0)
+        );
+    }
+
+    #[test]
+    fn test_currencies_are_kept_apart() {
+        let usd = Currency::default();
+        let jpy: Currency = "JPY".parse().unwrap();
+        let available = LedgerAccount::Client(1, Bucket::Available);
+        let mut ledger = Ledger::default();
+
+        ledger.post(JournalEntry::transfer(
+            usd,
+            LedgerAccount::Settlement,
+            available,
+            dec!(10),
+        ));
+        ledger.post(JournalEntry::transfer(
+            jpy,
+            LedgerAccount::Settlement,
+            available,
+            dec!(1500),
+        ));
+
+        assert_eq!(ledger.balance(usd, available), dec!(10));
+        assert_eq!(ledger.balance(jpy, available), dec!(1500));
+        assert_eq!(ledger.trial_balances().len(), 2);
+        assert!(ledger.is_balanced());
+    }
+}
@@ -0,0 +1,664 @@
+use super::account::ClientId;
+use super::currency::Currency;
+use super::error::ProcessingError;
+use super::policy::ExpiryAction;
+use super::timestamp::Timestamp;
+use super::transaction::{Authorize, Transaction, TransactionId};
+use super::Decimal;
+
+/// Dispute lifecycle state of a retained transaction.
+///
+/// ```text
+/// Applied ──dispute──▶ Disputed ──chargeback──▶ ChargedBack (final)
+///                       │    ▲
+///                resolve│    │dispute (if re-disputes are allowed)
+///                       ▼    │
+///                      Resolved
+/// ```
+///
+/// With partial disputes a transaction stays `Disputed` as long as any part of it is held.
+/// Deposits can also be refunded in parts, once the whole amount is refunded the deposit is
+/// `Refunded`, which is final as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionState {
+    /// Applied to the account and never disputed
+    Applied,
+    /// Currently under dispute, funds are held
+    Disputed,
+    /// A dispute was resolved and the held funds released
+    Resolved,
+    /// A dispute ended in a chargeback, this is a final state
+    ChargedBack,
+    /// The whole amount was refunded to the payer, this is a final state
+    Refunded,
+}
+
+impl std::fmt::Display for TransactionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionState::Applied => write!(f, "applied"),
+            TransactionState::Disputed => write!(f, "disputed"),
+            TransactionState::Resolved => write!(f, "resolved"),
+            TransactionState::ChargedBack => write!(f, "chargedback"),
+            TransactionState::Refunded => write!(f, "refunded"),
+        }
+    }
+}
+
+/// Settlement state of an authorized withdrawal.
+///
+/// ```text
+/// Pending ──capture──▶ Captured (final)
+///    │
+///    └──────void─────▶ Voided (final)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorizationState {
+    /// Funds are reserved in the authorized balance
+    Pending,
+    /// The withdrawal was finalized and the funds left the account
+    Captured,
+    /// The withdrawal was cancelled and the funds released
+    Voided,
+}
+
+impl std::fmt::Display for AuthorizationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthorizationState::Pending => write!(f, "pending"),
+            AuthorizationState::Captured => write!(f, "captured"),
+            AuthorizationState::Voided => write!(f, "voided"),
+        }
+    }
+}
+
+/// Dispute bookkeeping of a retained transaction.
+///
+/// Tracks the lifecycle state together with the cumulative amounts that are currently
+/// disputed (held), that were charged back and that were refunded,
+/// so several partial disputes and refunds can coexist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Lifecycle {
+    state: TransactionState,
+    /// Amount of the original transaction
+    amount: Decimal,
+    /// Amount currently under dispute
+    disputed: Decimal,
+    /// Amount that was charged back
+    charged_back: Decimal,
+    /// Amount that was refunded to the payer
+    refunded: Decimal,
+    /// Amount that was disputed and resolved, which can't be disputed again unless re-disputes
+    /// are allowed
+    resolved: Decimal,
+}
+
+impl Lifecycle {
+    pub(super) fn new(amount: Decimal) -> Self {
+        Self {
+            state: TransactionState::Applied,
+            amount,
+            disputed: Decimal::ZERO,
+            charged_back: Decimal::ZERO,
+            refunded: Decimal::ZERO,
+            resolved: Decimal::ZERO,
+        }
+    }
+
+    pub(super) fn state(&self) -> TransactionState {
+        self.state
+    }
+
+    pub(super) fn disputed(&self) -> Decimal {
+        self.disputed
+    }
+
+    /// Amount of the original transaction that is neither disputed, charged back nor refunded
+    pub(super) fn undisputed(&self) -> Decimal {
+        self.amount - self.disputed - self.charged_back - self.refunded
+    }
+
+    /// Returns the lifecycle after disputing `requested` (everything undisputed if `None`)
+    /// together with the amount to hold, or why the dispute is not allowed.
+    ///
+    /// Without `allow_redispute` the resolved part can't be disputed again, the part that was
+    /// never disputed still can.
+    pub(super) fn dispute(
+        self,
+        tx: TransactionId,
+        requested: Option<Decimal>,
+        allow_redispute: bool,
+    ) -> Result<(Self, Decimal), ProcessingError> {
+        match self.state {
+            TransactionState::ChargedBack => {
+                return Err(ProcessingError::AlreadyChargedBack { tx })
+            }
+            TransactionState::Refunded => return Err(ProcessingError::AlreadyRefunded { tx }),
+            TransactionState::Disputed if requested.is_none() || self.undisputed().is_zero() => {
+                return Err(ProcessingError::AlreadyUnderDispute { tx })
+            }
+            _ => {}
+        }
+
+        let undisputed = self.undisputed();
+        let remaining = if allow_redispute {
+            undisputed
+        } else {
+            (undisputed - self.resolved).max(Decimal::ZERO)
+        };
+        let amount = requested.unwrap_or(remaining);
+        let redisputed = amount.is_zero() || amount > remaining;
+        if !allow_redispute && !self.resolved.is_zero() && redisputed && amount <= undisputed {
+            return Err(ProcessingError::RedisputeNotAllowed { tx });
+        }
+        if amount > remaining {
+            return Err(ProcessingError::ExceedsUndisputedAmount {
+                tx,
+                requested: amount,
+                remaining,
+            });
+        }
+
+        let next = Self {
+            state: TransactionState::Disputed,
+            disputed: self.disputed + amount,
+            ..self
+        };
+        Ok((next, amount))
+    }
+
+    /// Returns the lifecycle after resolving `requested` (everything disputed if `None`)
+    /// together with the amount to release, or why the resolve is not allowed.
+    pub(super) fn resolve(
+        self,
+        tx: TransactionId,
+        requested: Option<Decimal>,
+    ) -> Result<(Self, Decimal), ProcessingError> {
+        let amount = self.settled_amount(tx, requested)?;
+        let mut next = Self {
+            disputed: self.disputed - amount,
+            resolved: self.resolved + amount,
+            ..self
+        };
+        next.state = next.settled_state(TransactionState::Resolved);
+        Ok((next, amount))
+    }
+
+    /// Returns the lifecycle after charging back `requested` (everything disputed if `None`)
+    /// together with the amount to charge back, or why the chargeback is not allowed.
+    pub(super) fn chargeback(
+        self,
+        tx: TransactionId,
+        requested: Option<Decimal>,
+    ) -> Result<(Self, Decimal), ProcessingError> {
+        let amount = self.settled_amount(tx, requested)?;
+        let mut next = Self {
+            disputed: self.disputed - amount,
+            charged_back: self.charged_back + amount,
+            ..self
+        };
+        next.state = next.settled_state(TransactionState::ChargedBack);
+        Ok((next, amount))
+    }
+
+    /// Returns the lifecycle after refunding `requested` (everything undisputed if `None`)
+    /// together with the amount to refund, or why the refund is not allowed.
+    ///
+    /// Only the undisputed part can be refunded, and a refunded part can't be disputed anymore.
+    pub(super) fn refund(
+        self,
+        tx: TransactionId,
+        requested: Option<Decimal>,
+    ) -> Result<(Self, Decimal), ProcessingError> {
+        if self.state == TransactionState::Refunded {
+            return Err(ProcessingError::AlreadyRefunded { tx });
+        }
+
+        let refundable = self.undisputed();
+        let amount = requested.unwrap_or(refundable);
+        if amount.is_zero() || amount > refundable {
+            return Err(ProcessingError::ExceedsRefundableAmount {
+                tx,
+                requested: amount,
+                refundable,
+            });
+        }
+
+        let mut next = Self {
+            refunded: self.refunded + amount,
+            ..self
+        };
+        if next.refunded == next.amount {
+            next.state = TransactionState::Refunded;
+        }
+        Ok((next, amount))
+    }
+
+    /// Validate the amount a resolve or chargeback settles.
+    fn settled_amount(
+        self,
+        tx: TransactionId,
+        requested: Option<Decimal>,
+    ) -> Result<Decimal, ProcessingError> {
+        match self.state {
+            TransactionState::Disputed => {}
+            TransactionState::ChargedBack => {
+                return Err(ProcessingError::AlreadyChargedBack { tx })
+            }
+            TransactionState::Applied | TransactionState::Resolved | TransactionState::Refunded => {
+                return Err(ProcessingError::NotUnderDispute { tx })
+            }
+        }
+
+        let amount = requested.unwrap_or(self.disputed);
+        if amount > self.disputed {
+            return Err(ProcessingError::ExceedsDisputedAmount {
+                tx,
+                requested: amount,
+                disputed: self.disputed,
+            });
+        }
+        Ok(amount)
+    }
+
+    /// State once a settlement was applied: still disputed while anything is held,
+    /// otherwise charged back if any part was charged back, otherwise `outcome`.
+    fn settled_state(&self, outcome: TransactionState) -> TransactionState {
+        if !self.disputed.is_zero() {
+            TransactionState::Disputed
+        } else if !self.charged_back.is_zero() {
+            TransactionState::ChargedBack
+        } else {
+            outcome
+        }
+    }
+}
+
+/// A dispute the engine settled on its own because it outlived the dispute window.
+///
+/// The settlement is applied as a synthetic `resolve` or `chargeback` row without amount,
+/// these events record it for reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpiredDispute {
+    client_id: ClientId,
+    transaction_id: TransactionId,
+    currency: Currency,
+    amount: Decimal,
+    action: ExpiryAction,
+    row: u64,
+    timestamp: Option<Timestamp>,
+}
+
+impl ExpiredDispute {
+    pub(super) fn new(
+        client_id: ClientId,
+        transaction_id: TransactionId,
+        currency: Currency,
+        amount: Decimal,
+        action: ExpiryAction,
+        row: u64,
+        timestamp: Option<Timestamp>,
+    ) -> Self {
+        Self {
+            client_id,
+            transaction_id,
+            currency,
+            amount,
+            action,
+            row,
+            timestamp,
+        }
+    }
+
+    /// Returns the client that owns the disputed transaction
+    pub fn client_id(&self) -> ClientId {
+        self.client_id
+    }
+
+    /// Returns the disputed transaction
+    pub fn transaction_id(&self) -> TransactionId {
+        self.transaction_id
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Returns the disputed amount that was settled
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    /// Returns how the dispute was settled
+    pub fn action(&self) -> ExpiryAction {
+        self.action
+    }
+
+    /// Returns the row after which the dispute expired
+    pub fn row(&self) -> u64 {
+        self.row
+    }
+
+    /// Returns the latest timestamp seen when the dispute expired, if the input carries
+    /// timestamps
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+/// A retained transaction stored together with its dispute lifecycle.
+#[derive(Debug, Clone)]
+pub(super) struct Tracked<T> {
+    transaction: T,
+    lifecycle: Lifecycle,
+}
+
+impl<T> Tracked<T> {
+    pub(super) fn new(transaction: T, amount: Decimal) -> Self {
+        Self {
+            transaction,
+            lifecycle: Lifecycle::new(amount),
+        }
+    }
+
+    pub(super) fn transaction(&self) -> &T {
+        &self.transaction
+    }
+
+    pub(super) fn lifecycle(&self) -> Lifecycle {
+        self.lifecycle
+    }
+
+    pub(super) fn state(&self) -> TransactionState {
+        self.lifecycle.state()
+    }
+
+    pub(super) fn set_lifecycle(&mut self, lifecycle: Lifecycle) {
+        self.lifecycle = lifecycle;
+    }
+
+    /// Returns a snapshot of the transaction and its dispute status for queries
+    pub(super) fn retained(&self, wrap: impl FnOnce(T) -> Transaction) -> RetainedTransaction
+    where
+        T: Clone,
+    {
+        RetainedTransaction {
+            transaction: wrap(self.transaction.clone()),
+            state: self.lifecycle.state(),
+            disputed: self.lifecycle.disputed(),
+        }
+    }
+}
+
+/// A retained deposit, withdrawal or transfer together with its dispute status.
+///
+/// Returned by `PaymentEngine::get_transaction` and `PaymentEngine::open_disputes`, it is a
+/// snapshot that doesn't follow later rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetainedTransaction {
+    transaction: Transaction,
+    state: TransactionState,
+    disputed: Decimal,
+}
+
+impl RetainedTransaction {
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Returns the client that owns the transaction, the sender of a transfer
+    pub fn client_id(&self) -> ClientId {
+        self.transaction.client_id()
+    }
+
+    /// Returns the dispute lifecycle state of the transaction
+    pub fn state(&self) -> TransactionState {
+        self.state
+    }
+
+    /// Returns the amount that is currently under dispute
+    pub fn disputed(&self) -> Decimal {
+        self.disputed
+    }
+}
+
+/// An authorized withdrawal stored together with its settlement state.
+#[derive(Debug, Clone)]
+pub(super) struct Authorization {
+    authorize: Authorize,
+    state: AuthorizationState,
+}
+
+impl Authorization {
+    pub(super) fn new(authorize: Authorize) -> Self {
+        Self {
+            authorize,
+            state: AuthorizationState::Pending,
+        }
+    }
+
+    pub(super) fn authorize(&self) -> &Authorize {
+        &self.authorize
+    }
+
+    pub(super) fn state(&self) -> AuthorizationState {
+        self.state
+    }
+
+    /// Returns the authorization settled to `outcome`, or why it can't be settled anymore.
+    pub(super) fn settle(
+        &self,
+        outcome: AuthorizationState,
+    ) -> Result<AuthorizationState, ProcessingError> {
+        if self.state != AuthorizationState::Pending {
+            return Err(ProcessingError::AuthorizationNotPending {
+                tx: self.authorize.transaction_id(),
+                state: self.state,
+            });
+        }
+        Ok(outcome)
+    }
+
+    pub(super) fn set_state(&mut self, state: AuthorizationState) {
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn disputed(amount: Decimal) -> Lifecycle {
+        Lifecycle::new(amount).dispute(1, None, true).unwrap().0
+    }
+
+    #[test]
+    fn test_full_dispute_from_applied() {
+        let (next, amount) = Lifecycle::new(dec!(100)).dispute(1, None, true).unwrap();
+        assert_eq!(next.state(), TransactionState::Disputed);
+        assert_eq!(amount, dec!(100));
+        assert_eq!(next.disputed(), dec!(100));
+    }
+
+    #[test]
+    fn test_redispute_after_resolve_is_configurable() {
+        let (state, _) = disputed(dec!(100)).resolve(1, None).unwrap();
+        assert_eq!(state.state(), TransactionState::Resolved);
+
+        assert!(state.dispute(1, None, true).is_ok());
+        assert!(matches!(
+            state.dispute(1, None, false),
+            Err(ProcessingError::RedisputeNotAllowed { tx: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_undisputed_part_can_be_disputed_after_partial_resolve() {
+        let (state, _) = Lifecycle::new(dec!(100))
+            .dispute(1, Some(dec!(40)), false)
+            .unwrap();
+        let (state, _) = state.resolve(1, None).unwrap();
+
+        let (next, amount) = state.dispute(1, None, false).unwrap();
+        assert_eq!(amount, dec!(60));
+        assert_eq!(next.disputed(), dec!(60));
+        assert!(matches!(
+            state.dispute(1, Some(dec!(70)), false),
+            Err(ProcessingError::RedisputeNotAllowed { tx: 1 })
+        ));
+        assert!(matches!(
+            next.resolve(1, None).unwrap().0.dispute(1, None, false),
+            Err(ProcessingError::RedisputeNotAllowed { tx: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_charged_back_is_final() {
+        let (state, _) = disputed(dec!(100)).chargeback(1, None).unwrap();
+        assert_eq!(state.state(), TransactionState::ChargedBack);
+
+        assert!(matches!(
+            state.dispute(1, None, true),
+            Err(ProcessingError::AlreadyChargedBack { tx: 1 })
+        ));
+        assert!(matches!(
+            state.resolve(1, None),
+            Err(ProcessingError::AlreadyChargedBack { tx: 1 })
+        ));
+        assert!(matches!(
+            state.chargeback(1, None),
+            Err(ProcessingError::AlreadyChargedBack { tx: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_and_chargeback_require_dispute() {
+        let applied = Lifecycle::new(dec!(100));
+        let (resolved, _) = disputed(dec!(100)).resolve(1, None).unwrap();
+        for state in [applied, resolved] {
+            assert!(matches!(
+                state.resolve(1, None),
+                Err(ProcessingError::NotUnderDispute { tx: 1 })
+            ));
+            assert!(matches!(
+                state.chargeback(1, None),
+                Err(ProcessingError::NotUnderDispute { tx: 1 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_double_full_dispute_is_rejected() {
+        assert!(matches!(
+            disputed(dec!(100)).dispute(1, None, true),
+            Err(ProcessingError::AlreadyUnderDispute { tx: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_partial_disputes_coexist() {
+        let state = Lifecycle::new(dec!(100));
+        let (state, amount) = state.dispute(1, Some(dec!(30)), true).unwrap();
+        assert_eq!(amount, dec!(30));
+        let (state, amount) = state.dispute(1, Some(dec!(50)), true).unwrap();
+        assert_eq!(amount, dec!(50));
+
+        assert_eq!(state.disputed(), dec!(80));
+        assert_eq!(state.undisputed(), dec!(20));
+        assert!(matches!(
+            state.dispute(1, Some(dec!(25)), true),
+            Err(ProcessingError::ExceedsUndisputedAmount { tx: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_partial_resolve_keeps_transaction_disputed() {
+        let (state, _) = Lifecycle::new(dec!(100))
+            .dispute(1, Some(dec!(60)), true)
+            .unwrap();
+        let (state, amount) = state.resolve(1, Some(dec!(20))).unwrap();
+        assert_eq!(amount, dec!(20));
+        assert_eq!(state.state(), TransactionState::Disputed);
+        assert_eq!(state.disputed(), dec!(40));
+
+        assert!(matches!(
+            state.resolve(1, Some(dec!(50))),
+            Err(ProcessingError::ExceedsDisputedAmount { tx: 1, .. })
+        ));
+
+        let (state, amount) = state.resolve(1, None).unwrap();
+        assert_eq!(amount, dec!(40));
+        assert_eq!(state.state(), TransactionState::Resolved);
+    }
+
+    #[test]
+    fn test_partial_chargeback_then_resolve_ends_charged_back() {
+        let (state, _) = disputed(dec!(100)).chargeback(1, Some(dec!(30))).unwrap();
+        assert_eq!(state.state(), TransactionState::Disputed);
+
+        let (state, amount) = state.resolve(1, None).unwrap();
+        assert_eq!(amount, dec!(70));
+        assert_eq!(state.state(), TransactionState::ChargedBack);
+    }
+
+    #[test]
+    fn test_partial_refunds_up_to_amount() {
+        let (state, amount) = Lifecycle::new(dec!(100)).refund(1, Some(dec!(30))).unwrap();
+        assert_eq!(amount, dec!(30));
+        assert_eq!(state.state(), TransactionState::Applied);
+
+        assert!(matches!(
+            state.refund(1, Some(dec!(80))),
+            Err(ProcessingError::ExceedsRefundableAmount { tx: 1, .. })
+        ));
+
+        let (state, amount) = state.refund(1, None).unwrap();
+        assert_eq!(amount, dec!(70));
+        assert_eq!(state.state(), TransactionState::Refunded);
+        assert!(matches!(
+            state.refund(1, None),
+            Err(ProcessingError::AlreadyRefunded { tx: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_refunded_part_cannot_be_disputed() {
+        let (state, _) = Lifecycle::new(dec!(100)).refund(1, Some(dec!(60))).unwrap();
+        assert!(matches!(
+            state.dispute(1, Some(dec!(50)), true),
+            Err(ProcessingError::ExceedsUndisputedAmount { tx: 1, .. })
+        ));
+
+        let (state, amount) = state.dispute(1, None, true).unwrap();
+        assert_eq!(amount, dec!(40));
+        assert_eq!(state.disputed(), dec!(40));
+    }
+
+    #[test]
+    fn test_disputed_part_cannot_be_refunded() {
+        let (state, _) = disputed(dec!(100)).resolve(1, Some(dec!(25))).unwrap();
+        let (state, amount) = state.refund(1, None).unwrap();
+        assert_eq!(amount, dec!(25));
+        assert_eq!(state.state(), TransactionState::Disputed);
+
+        assert!(matches!(
+            state.refund(1, None),
+            Err(ProcessingError::ExceedsRefundableAmount { tx: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_fully_refunded_is_final() {
+        let (state, _) = Lifecycle::new(dec!(100)).refund(1, None).unwrap();
+        assert_eq!(state.state(), TransactionState::Refunded);
+        assert!(matches!(
+            state.dispute(1, None, true),
+            Err(ProcessingError::AlreadyRefunded { tx: 1 })
+        ));
+        assert!(matches!(
+            state.resolve(1, None),
+            Err(ProcessingError::NotUnderDispute { tx: 1 })
+        ));
+    }
+}
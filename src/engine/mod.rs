@@ -2,16 +2,57 @@
 //!
 //! This module contains the core payment processing logic including:
 //! - `PaymentEngine` - The main transaction processor
-//! - `Account` - Client account state management
-//! - `Transaction` types - Deposit, Withdrawal, Dispute, Resolve, Chargeback
+//! - `Account` - Client account state management, one account per client and currency
+//! - `ExportFormat` - Columns of the exported accounts
+//! - `Currency` - Currency codes and their decimal scales
+//! - `Timestamp` - Optional point in time of a row
+//! - `AccountStatus` - Freezes, restrictions and closure of accounts
+//! - `TransactionState` - Dispute lifecycle of retained transactions
+//! - `AuthorizationState` - Settlement of two-phase withdrawals
+//! - `Transaction` types - Deposit, Withdrawal, Dispute, Resolve, Chargeback, Convert
+//! - `EnginePolicy` / `PaymentEngineBuilder` - Configurable business rules
+//! - `FeeSchedule` - Fees charged on deposits and withdrawals
+//! - `RateTable` - Exchange rates of currency conversions
+//! - `Ledger` - Double-entry general ledger behind the account balances
+//! - `Outcome` - What applying a single transaction did
+//! - `ProcessingReport` - Summary of the rows of a `process_transactions` call
 //! - `Error` types - Processing and validation errors
 
 mod account;
+mod accounts;
+mod builder;
+mod currency;
 mod error;
+mod fees;
+mod fx;
+mod ledger;
+mod lifecycle;
 mod payment_engine;
+mod policy;
+mod reorder;
+mod report;
+mod status;
+mod timestamp;
 mod transaction;
 
 pub(crate) use rust_decimal::Decimal;
 
-pub use account::Account;
-pub use payment_engine::PaymentEngine;
+pub use account::{Account, ClientId, ExportFormat};
+pub use builder::PaymentEngineBuilder;
+pub use currency::Currency;
+pub use error::{Error, ProcessingError, TransactionError};
+pub use fees::{Fee, FeeSchedule, FeeTier};
+pub use fx::{Conversion, ExchangeRate, RateTable};
+pub use ledger::{Bucket, JournalEntry, Ledger, LedgerAccount, Posting};
+pub use lifecycle::{AuthorizationState, ExpiredDispute, RetainedTransaction, TransactionState};
+pub use payment_engine::{Outcome, PaymentEngine};
+pub use policy::{ClientConfig, EnginePolicy, ExpiryAction};
+pub use reorder::UnmatchedRow;
+pub use report::{ProcessingReport, RejectedRow};
+pub use status::{AccountStatus, FreezeReason};
+pub use timestamp::Timestamp;
+pub use transaction::{
+    Adjustment, Authorize, Capture, Chargeback, Close, Convert, CreditLimit, Deposit, Dispute,
+    Freeze, Refund, Resolve, Restrict, Transaction, TransactionId, TransactionRecord,
+    TransactionType, Transfer, Unfreeze, Unlock, Void, Withdrawal,
+};
@@ -1,58 +1,216 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::{Read, Write};
 
-use super::account::ClientId;
+use super::account::{BasicAccountRow, ClientId, ExportFormat};
+use super::accounts::Accounts;
+use super::builder::PaymentEngineBuilder;
+use super::currency::Currency;
 use super::error::{Error, ProcessingError};
+use super::fx::{Conversion, RateTable};
+use super::ledger::{Bucket, Ledger, LedgerAccount};
+use super::lifecycle::{
+    Authorization, AuthorizationState, ExpiredDispute, Lifecycle, RetainedTransaction, Tracked,
+    TransactionState,
+};
+use super::policy::{ClientConfig, EnginePolicy, ExpiryAction};
+use super::reorder::{ReorderBuffer, UnmatchedRow};
+use super::report::ProcessingReport;
+use super::status::{FreezeReason, Restriction};
+use super::timestamp::Timestamp;
 use super::transaction::{
-    Chargeback, Deposit, Dispute, Resolve, Transaction, TransactionId, TransactionRecord,
-    Withdrawal,
+    Adjustment, Authorize, Capture, Chargeback, Close, Convert, CreditLimit, Deposit, Dispute,
+    Freeze, Refund, Resolve, Restrict, Transaction, TransactionId, TransactionRecord, Transfer,
+    Unfreeze, Unlock, Void, Withdrawal,
 };
+use super::Decimal;
 
-// Export this for testing purposes
 use super::account::Account;
 
 /// The core payment processing engine.
 ///
-/// Processes transactions (deposits, withdrawals, disputes, resolves, chargebacks)
-/// and maintains account state for all clients.
+/// Processes transactions (deposits, withdrawals, two-phase withdrawals, transfers, disputes,
+/// resolves, chargebacks, refunds and administrative status changes) and maintains account state
+/// for all clients.
 #[derive(Debug, Default)]
 pub struct PaymentEngine {
-    /// Maps client ID to their account state
-    accounts: HashMap<ClientId, Account>,
-    /// Maps transaction ID to successful deposits for dispute lookups
-    deposits: HashMap<TransactionId, Deposit>,
-    /// Set of disputed transactions (Under dispute)
-    disputes: HashSet<TransactionId>,
+    /// Account state of every client, one account per currency
+    accounts: Accounts,
+    /// Maps transaction ID to successful deposits and their dispute state for dispute lookups
+    deposits: HashMap<TransactionId, Tracked<Deposit>>,
+    /// Maps transaction ID to successful withdrawals and their dispute state for dispute lookups
+    withdrawals: HashMap<TransactionId, Tracked<Withdrawal>>,
+    /// Maps transaction ID to successful transfers and their dispute state for dispute lookups
+    transfers: HashMap<TransactionId, Tracked<Transfer>>,
+    /// Maps transaction ID to authorized withdrawals and their settlement state
+    authorizations: HashMap<TransactionId, Authorization>,
+    /// Maps transaction ID to operator adjustments, kept apart from deposits and withdrawals
+    /// because they can't be disputed
+    adjustments: HashMap<TransactionId, Adjustment>,
+    /// Maps transaction ID to applied currency conversions and the rates they were booked at
+    conversions: HashMap<TransactionId, Conversion>,
+    /// Maps transaction ID to the fingerprint of every applied transaction that carries its own
+    /// ID, including administrative actions. The details stay in the maps above.
+    /// Transaction IDs are globally unique, so this is used to reject reuse.
+    applied: HashMap<TransactionId, u64>,
+    /// Funds waiting in the pending or reserved balance, keyed by release ID
+    releases: HashMap<u64, ScheduledRelease>,
+    /// Scheduled releases ordered by the row count after which they are released
+    release_rows: BTreeSet<(u64, u64)>,
+    /// Scheduled releases ordered by the timestamp at which they are released
+    release_deadlines: BTreeSet<(Timestamp, u64)>,
+    /// ID of the next scheduled release
+    next_release_id: u64,
+    /// Disputed transactions ordered by the row count after which the dispute expires
+    expiries: BTreeSet<(u64, TransactionId)>,
+    /// Disputed transactions ordered by the timestamp at which the dispute expires
+    expiry_deadlines: BTreeSet<(Timestamp, TransactionId)>,
+    /// Expiry window of each open dispute, to take it out of the queues once it is settled
+    expiry_windows: HashMap<TransactionId, DueAt>,
+    /// Disputes the engine settled because they outlived the dispute window
+    expired_disputes: Vec<ExpiredDispute>,
+    /// Number of rows seen so far, drives the deposit clearing period, rolling reserves and
+    /// dispute expiry
+    rows: u64,
+    /// Latest timestamp seen so far, drives the time windows of rows without a timestamp
+    clock: Option<Timestamp>,
+    /// Per-client settings from the policy
+    clients: HashMap<ClientId, ClientConfig>,
+    /// Double-entry ledger every balance change is posted to, the account balances are derived
+    /// from the same journal entries
+    ledger: Ledger,
+    /// Exchange rates of currency conversions
+    rates: RateTable,
+    /// Rows waiting for the transaction they reference
+    reorder: ReorderBuffer,
+    /// Rows that never found the transaction they reference
+    unmatched: Vec<UnmatchedRow>,
+    /// Configurable engine behavior
+    policy: EnginePolicy,
+}
+
+/// What applying a transaction did to the engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The transaction was applied to the accounts
+    Applied,
+    /// The transaction is an exact replay of an applied one and was ignored, see
+    /// `EnginePolicy::idempotent_replays`
+    Replayed,
+}
+
+/// Balance that scheduled funds are released from
+#[derive(Debug, Clone, Copy)]
+enum ReleaseFrom {
+    /// An uncleared deposit
+    Pending,
+    /// The reserved part of a deposit
+    Reserve,
+}
+
+/// Funds that become available once enough rows were seen or enough time passed
+#[derive(Debug, Clone, Copy)]
+struct ScheduledRelease {
+    client_id: ClientId,
+    currency: Currency,
+    amount: Decimal,
+    from: ReleaseFrom,
+    due: DueAt,
+}
+
+/// When a dispute expires or funds are released, by row count and by timestamp, whichever
+/// comes first
+#[derive(Debug, Clone, Copy)]
+struct DueAt {
+    row: Option<u64>,
+    deadline: Option<Timestamp>,
+}
+
+impl DueAt {
+    /// Due once `rows` further rows were seen after `row` or `days` passed since `since`,
+    /// a zero count disables its window
+    fn after(row: u64, rows: u64, since: Option<Timestamp>, days: u64) -> Self {
+        Self {
+            row: Some(row + rows).filter(|_| rows > 0),
+            deadline: since.filter(|_| days > 0).map(|since| since.add_days(days)),
+        }
+    }
+
+    /// Returns whether any window is set
+    fn is_scheduled(&self) -> bool {
+        self.row.is_some() || self.deadline.is_some()
+    }
 }
 
 impl PaymentEngine {
     /// Create a new `PaymentEngine` with empty accounts and transactions
     pub fn new() -> Self {
-        log::trace!("PaymentEngine initialized");
+        Self::with_policy(EnginePolicy::default())
+    }
+
+    /// Create a builder to configure the engine's `EnginePolicy`
+    pub fn builder() -> PaymentEngineBuilder {
+        PaymentEngineBuilder::default()
+    }
+
+    /// Create a new `PaymentEngine` with empty accounts and transactions, using the given policy
+    pub fn with_policy(policy: EnginePolicy) -> Self {
+        log::trace!("PaymentEngine initialized with {policy:?}");
         Self {
-            accounts: HashMap::new(),
+            accounts: Accounts::default(),
             deposits: HashMap::new(),
-            disputes: HashSet::new(),
+            withdrawals: HashMap::new(),
+            transfers: HashMap::new(),
+            authorizations: HashMap::new(),
+            adjustments: HashMap::new(),
+            conversions: HashMap::new(),
+            applied: HashMap::new(),
+            releases: HashMap::new(),
+            release_rows: BTreeSet::new(),
+            release_deadlines: BTreeSet::new(),
+            next_release_id: 0,
+            expiries: BTreeSet::new(),
+            expiry_deadlines: BTreeSet::new(),
+            expiry_windows: HashMap::new(),
+            expired_disputes: Vec::new(),
+            rows: 0,
+            clock: None,
+            clients: policy
+                .clients
+                .iter()
+                .map(|config| (config.client, config.clone()))
+                .collect(),
+            ledger: Ledger::default(),
+            rates: RateTable::default(),
+            reorder: ReorderBuffer::new(policy.reorder_window_rows, policy.reorder_buffer_size),
+            unmatched: Vec::new(),
+            policy,
         }
     }
 
+    /// Use `rates` for currency conversions
+    pub(super) fn with_rates(mut self, rates: RateTable) -> Self {
+        self.rates = rates;
+        self
+    }
+
     /// Primary API: Process transactions from any source (File, `TcpStream`, etc.)
     /// Note that the CSV reader is buffered automatically, so you should not wrap rdr in a buffered reader like `io::BufReader`.
-    pub fn process_transactions<R: Read>(&mut self, reader: R) -> Result<(), Error> {
+    /// Returns a report of the processed rows, including every rejected row and the reason.
+    pub fn process_transactions<R: Read>(&mut self, reader: R) -> Result<ProcessingReport, Error> {
         log::info!("Starting transaction processing");
 
         let mut csv_reader = csv::ReaderBuilder::new()
             .trim(csv::Trim::All) // trim whitespace from fields
             .from_reader(reader);
 
-        let mut processed = 0u64;
-        let mut skipped = 0u64;
+        let mut report = ProcessingReport::default();
+        let mut row_num = 0u64;
 
         for result in csv_reader.deserialize() {
             // Step 1:Parse CSV record into raw dirty TransactionRecord
             let record: TransactionRecord = result?;
 
-            let row_num = processed + skipped + 1;
+            row_num += 1;
             log::trace!(
                 "[row {}] Parsing: type={:?} client={} tx={} amount={:?}",
                 row_num,
@@ -61,36 +219,78 @@ impl PaymentEngine {
                 record.tx,
                 record.amount
             );
+            let tx_type = record.tx_type;
+            report.read(tx_type);
 
             // Step 2: Convert raw dirty TransactionRecord into validated Transaction
-            let transaction = Transaction::try_from(record)?;
+            let transaction = Transaction::try_from(record.clone())?;
 
             // Step 3: Process validated Transaction
-            if let Err(e) = self.process_transaction(transaction) {
-                log::warn!("[row {row_num}] - Skipped: {e}");
-                skipped += 1;
-            } else {
-                processed += 1;
+            // Keep rows referencing transactions never seen in case their target arrives later
+            let parkable = self.reorder.is_enabled() && transaction.referenced_tx_id().is_some();
+            match self.process_transaction(transaction.clone()) {
+                Err(ProcessingError::TransactionNotFound { tx }) if parkable => {
+                    log::debug!("[row {row_num}] - Parked: waiting for transaction {tx}");
+                    if let Some(evicted) = self.reorder.park(row_num, record, transaction) {
+                        self.reject_unmatched(evicted, "reorder buffer is full", &mut report);
+                    }
+                }
+                Err(e) => {
+                    log::warn!("[row {row_num}] - Skipped: {e}");
+                    report.reject(row_num, record, &e);
+                }
+                Ok(outcome) => {
+                    report.apply(tx_type, &transaction, outcome);
+                    // Rows that arrived before this transaction can be applied now
+                    if let Some(tx_id) = transaction.transaction_id() {
+                        self.retry_parked(tx_id, &mut report);
+                    }
+                }
             }
+
+            for expired in self.reorder.take_expired(row_num) {
+                self.reject_unmatched(expired, "reorder window ended", &mut report);
+            }
+        }
+
+        for parked in self.reorder.drain() {
+            self.reject_unmatched(parked, "end of input", &mut report);
         }
 
         log::info!(
-            "Processing complete: {} processed, {} skipped, {} accounts",
-            processed,
-            skipped,
+            "Processing complete: {} applied, {} replayed, {} skipped, {} accounts",
+            report.applied(),
+            report.replayed(),
+            report.rejected(),
             self.accounts.len()
         );
-        Ok(())
+        Ok(report)
     }
 
     /// Secondary API: Write final state to any sink (Stdout, File, `TcpStream`, etc.)
     /// Note that the CSV writer is buffered automatically, so you should not wrap wtr in a buffered writer like `io::BufWriter`.
+    /// Balances are only exported if the ledger passes the trial balance check.
+    /// Writes the basic `client,currency,available,held,total,locked` columns, see
+    /// `export_accounts_as`.
     pub fn export_accounts<W: Write>(&self, writer: W) -> Result<(), Error> {
+        self.export_accounts_as(writer, ExportFormat::Basic)
+    }
+
+    /// Write final state like `export_accounts`, with the columns of `format`.
+    pub fn export_accounts_as<W: Write>(
+        &self,
+        writer: W,
+        format: ExportFormat,
+    ) -> Result<(), Error> {
         log::info!("Exporting {} accounts", self.accounts.len());
+        self.check_trial_balance()?;
 
         let mut csv_writer = csv::Writer::from_writer(writer);
-        for account in self.accounts.values() {
-            csv_writer.serialize(account)?;
+        for account in self.accounts.iter() {
+            match format {
+                ExportFormat::Basic => csv_writer.serialize(BasicAccountRow::from(account))?,
+                ExportFormat::Extended => csv_writer.serialize(account)?,
+            }
         }
         csv_writer.flush()?;
 
@@ -98,19 +298,432 @@ impl PaymentEngine {
         Ok(())
     }
 
-    /// Returns the number of accounts in the engine
+    /// Returns the double-entry ledger behind the account balances
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Reconcile the ledger with the accounts: every balance of every client account must match
+    /// its ledger account, and the ledger must not hold client funds of an account that doesn't
+    /// exist. Journal entries are balanced by construction, so the trial balance of each
+    /// currency is always zero.
+    pub fn check_trial_balance(&self) -> Result<(), Error> {
+        for (currency, client_id, bucket, balance) in self.ledger.client_balances() {
+            if !self.accounts.contains(client_id, currency) {
+                return Err(Error::Unbalanced(format!(
+                    "ledger has {currency} {bucket:?} balance {balance} of client {client_id}, \
+                     which has no {currency} account"
+                )));
+            }
+        }
+
+        for account in self.accounts.iter() {
+            for bucket in Bucket::ALL {
+                let ledger_balance = self.ledger.balance(
+                    account.currency(),
+                    LedgerAccount::Client(account.client_id(), bucket),
+                );
+                if account.balance(bucket) != ledger_balance {
+                    return Err(Error::Unbalanced(format!(
+                        "client {} has {} {:?} balance {}, ledger has {ledger_balance}",
+                        account.client_id(),
+                        account.currency(),
+                        bucket,
+                        account.balance(bucket)
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of accounts in the engine, counting every currency of a client
     pub fn account_count(&self) -> usize {
         self.accounts.len()
     }
 
-    fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ProcessingError> {
+    /// Returns the account of a client in a currency,
+    /// or `None` if the client has no account in that currency.
+    pub fn get_account(&self, client_id: ClientId, currency: Currency) -> Option<&Account> {
+        self.accounts.get(client_id, currency)
+    }
+
+    /// Returns every account of a client, ordered by currency
+    pub fn client_accounts(&self, client_id: ClientId) -> impl Iterator<Item = &Account> {
+        self.accounts.client(client_id)
+    }
+
+    /// Returns every account in the engine, in no particular order
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.iter()
+    }
+
+    /// Returns a retained deposit, withdrawal or transfer together with its dispute status,
+    /// or `None` if no such transaction was applied.
+    pub fn get_transaction(&self, tx_id: TransactionId) -> Option<RetainedTransaction> {
+        self.deposits
+            .get(&tx_id)
+            .map(|deposit| deposit.retained(Transaction::Deposit))
+            .or_else(|| {
+                self.withdrawals
+                    .get(&tx_id)
+                    .map(|withdrawal| withdrawal.retained(Transaction::Withdrawal))
+            })
+            .or_else(|| {
+                self.transfers
+                    .get(&tx_id)
+                    .map(|transfer| transfer.retained(Transaction::Transfer))
+            })
+    }
+
+    /// Returns the transactions of a client that are currently under dispute,
+    /// ordered by transaction ID. Disputed transfers belong to the sending client.
+    pub fn open_disputes(&self, client_id: ClientId) -> Vec<RetainedTransaction> {
+        let deposits = self
+            .deposits
+            .values()
+            .filter(|deposit| deposit.transaction().client_id() == client_id)
+            .filter(|deposit| deposit.state() == TransactionState::Disputed)
+            .map(|deposit| deposit.retained(Transaction::Deposit));
+        let withdrawals = self
+            .withdrawals
+            .values()
+            .filter(|withdrawal| withdrawal.transaction().client_id() == client_id)
+            .filter(|withdrawal| withdrawal.state() == TransactionState::Disputed)
+            .map(|withdrawal| withdrawal.retained(Transaction::Withdrawal));
+        let transfers = self
+            .transfers
+            .values()
+            .filter(|transfer| transfer.transaction().client_id() == client_id)
+            .filter(|transfer| transfer.state() == TransactionState::Disputed)
+            .map(|transfer| transfer.retained(Transaction::Transfer));
+
+        let mut disputes: Vec<_> = deposits.chain(withdrawals).chain(transfers).collect();
+        disputes.sort_by_key(|dispute| dispute.transaction().transaction_id());
+        disputes
+    }
+
+    /// Returns the dispute lifecycle state of a retained transaction,
+    /// or `None` if no such transaction was applied.
+    pub fn transaction_state(&self, tx_id: TransactionId) -> Option<TransactionState> {
+        self.deposits
+            .get(&tx_id)
+            .map(Tracked::state)
+            .or_else(|| self.withdrawals.get(&tx_id).map(Tracked::state))
+            .or_else(|| self.transfers.get(&tx_id).map(Tracked::state))
+    }
+
+    /// Returns the amount of a retained transaction that is currently under dispute,
+    /// or `None` if no such transaction was applied.
+    pub fn disputed_amount(&self, tx_id: TransactionId) -> Option<Decimal> {
+        self.deposits
+            .get(&tx_id)
+            .map(|deposit| deposit.lifecycle().disputed())
+            .or_else(|| {
+                self.withdrawals
+                    .get(&tx_id)
+                    .map(|withdrawal| withdrawal.lifecycle().disputed())
+            })
+            .or_else(|| {
+                self.transfers
+                    .get(&tx_id)
+                    .map(|transfer| transfer.lifecycle().disputed())
+            })
+    }
+
+    /// Returns the settlement state of an authorized withdrawal,
+    /// or `None` if no such authorization was applied.
+    pub fn authorization_state(&self, tx_id: TransactionId) -> Option<AuthorizationState> {
+        self.authorizations.get(&tx_id).map(Authorization::state)
+    }
+
+    /// Returns the disputes the engine settled on its own after the dispute window ended,
+    /// in the order they expired
+    pub fn expired_disputes(&self) -> &[ExpiredDispute] {
+        &self.expired_disputes
+    }
+
+    /// Returns the rows that waited in the reorder buffer but never found the transaction they
+    /// reference, in the order they were given up
+    pub fn unmatched_rows(&self) -> &[UnmatchedRow] {
+        &self.unmatched
+    }
+
+    /// Returns an applied currency conversion with the rate it was booked at,
+    /// or `None` if no such conversion was applied.
+    pub fn conversion(&self, tx_id: TransactionId) -> Option<&Conversion> {
+        self.conversions.get(&tx_id)
+    }
+
+    /// Apply the parked rows that reference `tx_id` and add them to `report`.
+    /// Retried rows were already counted as rows when they were read.
+    fn retry_parked(&mut self, tx_id: TransactionId, report: &mut ProcessingReport) {
+        for parked in self.reorder.take_matching(tx_id) {
+            let (row, record, transaction) = parked.into_parts();
+            match self.apply_transaction(transaction.clone()) {
+                Ok(outcome) => {
+                    log::debug!("[row {row}] - Applied after transaction {tx_id} arrived");
+                    report.apply(record.tx_type, &transaction, outcome);
+                }
+                Err(e) => {
+                    log::warn!("[row {row}] - Skipped: {e}");
+                    report.reject(row, record, &e);
+                }
+            }
+        }
+    }
+
+    /// Record a parked row that never found the transaction it references.
+    fn reject_unmatched(
+        &mut self,
+        unmatched: UnmatchedRow,
+        reason: &str,
+        report: &mut ProcessingReport,
+    ) {
+        let error = ProcessingError::TransactionNotFound {
+            tx: unmatched.referenced_tx_id(),
+        };
+        log::warn!("[row {}] - Skipped: {error}, {reason}", unmatched.row());
+        report.reject_with(
+            unmatched.row(),
+            unmatched.record().clone(),
+            error.kind(),
+            format!("{error}, {reason}"),
+        );
+        self.unmatched.push(unmatched);
+    }
+
+    /// Apply a single transaction, e.g. one built with `Deposit::new`, without going through CSV.
+    ///
+    /// The transaction counts as a row for the policy's row windows just like a CSV row. Unlike
+    /// `process_transactions`, a transaction referencing an unknown transaction fails right away
+    /// instead of waiting in the reorder buffer.
+    pub fn apply(&mut self, transaction: Transaction) -> Result<Outcome, ProcessingError> {
+        self.process_transaction(transaction)
+    }
+
+    fn process_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<Outcome, ProcessingError> {
+        self.rows += 1;
+        self.clock = self.clock.max(transaction.timestamp());
+        let result = self.apply_transaction(transaction);
+        self.release_due_funds();
+        self.expire_disputes();
+        result
+    }
+
+    fn apply_transaction(&mut self, transaction: Transaction) -> Result<Outcome, ProcessingError> {
         log::trace!("Processing transaction: {transaction}");
-        match transaction {
+
+        let tx_id = transaction.transaction_id();
+        if let Some(tx_id) = tx_id {
+            if let Some(&fingerprint) = self.applied.get(&tx_id) {
+                if self.policy.idempotent_replays && fingerprint == transaction.fingerprint() {
+                    log::debug!("Ignoring replay of already applied transaction {tx_id}");
+                    return Ok(Outcome::Replayed);
+                }
+                return Err(ProcessingError::DuplicateTransaction { tx: tx_id });
+            }
+        }
+
+        match transaction.clone() {
             Transaction::Deposit(deposit) => self.handle_deposit(deposit),
             Transaction::Withdrawal(withdrawal) => self.handle_withdrawal(withdrawal),
+            Transaction::Transfer(transfer) => self.handle_transfer(transfer),
+            Transaction::Convert(convert) => self.handle_convert(convert),
+            Transaction::Authorize(authorize) => self.handle_authorize(authorize),
+            Transaction::Capture(capture) => self.handle_capture(capture),
+            Transaction::Void(void) => self.handle_void(void),
             Transaction::Dispute(dispute) => self.handle_dispute(dispute),
             Transaction::Resolve(resolve) => self.handle_resolve(resolve),
             Transaction::Chargeback(chargeback) => self.handle_chargeback(chargeback),
+            Transaction::Refund(refund) => self.handle_refund(refund),
+            Transaction::Adjustment(adjustment) => self.handle_adjustment(adjustment),
+            Transaction::Freeze(freeze) => self.handle_freeze(freeze),
+            Transaction::Unfreeze(unfreeze) => self.handle_unfreeze(unfreeze),
+            Transaction::Unlock(unlock) => self.handle_unlock(unlock),
+            Transaction::Restrict(restrict) => self.handle_restrict(restrict),
+            Transaction::CreditLimit(credit_limit) => self.handle_credit_limit(credit_limit),
+            Transaction::Close(close) => self.handle_close(close),
+        }?;
+
+        if let Some(tx_id) = tx_id {
+            self.applied.insert(tx_id, transaction.fingerprint());
+        }
+        Ok(Outcome::Applied)
+    }
+
+    /// Credit a charged fee to the house account, which is opened if it doesn't exist.
+    /// Fees are revenue of the operator, so they are collected even if the house account is
+    /// locked.
+    fn collect_fee(&mut self, fee: Decimal, currency: Currency) {
+        let Some(house_id) = self.policy.fees.house_account.filter(|_| !fee.is_zero()) else {
+            return;
+        };
+        let house = self.accounts.get_or_open(house_id, currency, || {
+            new_account(&self.clients, house_id, currency)
+        });
+        self.ledger.post(house.deposit(fee));
+        log::trace!("[fee] client={house_id} amount={fee} {currency} collected");
+    }
+
+    /// Schedule funds to be released once `release.due` comes.
+    fn schedule_release(&mut self, release: ScheduledRelease) {
+        let id = self.next_release_id;
+        self.next_release_id += 1;
+        if let Some(row) = release.due.row {
+            self.release_rows.insert((row, id));
+        }
+        if let Some(deadline) = release.due.deadline {
+            self.release_deadlines.insert((deadline, id));
+        }
+        self.releases.insert(id, release);
+    }
+
+    /// Take a scheduled release out of the queues.
+    fn take_release(&mut self, id: u64) -> Option<ScheduledRelease> {
+        let release = self.releases.remove(&id)?;
+        if let Some(row) = release.due.row {
+            self.release_rows.remove(&(row, id));
+        }
+        if let Some(deadline) = release.due.deadline {
+            self.release_deadlines.remove(&(deadline, id));
+        }
+        Some(release)
+    }
+
+    /// Move every uncleared deposit and reserve whose period ended to available funds.
+    fn release_due_funds(&mut self) {
+        let mut due = Vec::new();
+        while let Some(&(row, id)) = self.release_rows.first() {
+            if row > self.rows {
+                break;
+            }
+            due.extend(self.take_release(id).map(|release| (id, release)));
+        }
+        while let Some(&(deadline, id)) = self.release_deadlines.first() {
+            if self.clock < Some(deadline) {
+                break;
+            }
+            due.extend(self.take_release(id).map(|release| (id, release)));
+        }
+        // Release in the order the funds were scheduled
+        due.sort_by_key(|&(id, _)| id);
+
+        for (_, release) in due {
+            let Some(account) = self.accounts.get_mut(release.client_id, release.currency) else {
+                continue;
+            };
+            let entry = match release.from {
+                ReleaseFrom::Pending => account.clear(release.amount),
+                ReleaseFrom::Reserve => account.release_reserve(release.amount),
+            };
+            self.ledger.post(entry);
+            log::trace!(
+                "[release] client={} amount={} {} from={:?} released",
+                release.client_id,
+                release.amount,
+                release.currency,
+                release.from
+            );
+        }
+    }
+
+    /// Start the expiry windows of a dispute that was opened on the current row at `opened_at`.
+    fn schedule_expiry(&mut self, tx_id: TransactionId, opened_at: Option<Timestamp>) {
+        let window = DueAt::after(
+            self.rows,
+            self.policy.dispute_expiry_rows,
+            opened_at,
+            self.policy.dispute_expiry_days,
+        );
+        if !window.is_scheduled() {
+            return;
+        }
+
+        if let Some(row) = window.row {
+            self.expiries.insert((row, tx_id));
+        }
+        if let Some(deadline) = window.deadline {
+            self.expiry_deadlines.insert((deadline, tx_id));
+        }
+        self.expiry_windows.insert(tx_id, window);
+    }
+
+    /// Stop the expiry of a dispute that is no longer open once `next` is applied.
+    fn end_expiry(&mut self, tx_id: TransactionId, next: &Lifecycle) {
+        if next.state() != TransactionState::Disputed {
+            self.cancel_expiry(tx_id);
+        }
+    }
+
+    /// Take a dispute out of the expiry queues.
+    fn cancel_expiry(&mut self, tx_id: TransactionId) {
+        let Some(window) = self.expiry_windows.remove(&tx_id) else {
+            return;
+        };
+        if let Some(row) = window.row {
+            self.expiries.remove(&(row, tx_id));
+        }
+        if let Some(deadline) = window.deadline {
+            self.expiry_deadlines.remove(&(deadline, tx_id));
+        }
+    }
+
+    /// Settle every dispute whose window ended with the policy's `dispute_expiry_action`.
+    ///
+    /// The settlement runs through the regular resolve and chargeback handlers as a synthetic
+    /// row without amount, so it follows the same rules. Settled disputes already left the
+    /// queues, see `end_expiry`.
+    fn expire_disputes(&mut self) {
+        let mut due = Vec::new();
+        while let Some(&(row, tx_id)) = self.expiries.first() {
+            if row > self.rows {
+                break;
+            }
+            due.push(tx_id);
+            self.cancel_expiry(tx_id);
+        }
+        while let Some(&(deadline, tx_id)) = self.expiry_deadlines.first() {
+            if self.clock < Some(deadline) {
+                break;
+            }
+            due.push(tx_id);
+            self.cancel_expiry(tx_id);
+        }
+
+        for tx_id in due {
+            if self.transaction_state(tx_id) != Some(TransactionState::Disputed) {
+                continue;
+            }
+            let Some((client_id, currency)) = self.retained_owner(tx_id) else {
+                continue;
+            };
+            let amount = self.disputed_amount(tx_id).unwrap_or_default();
+
+            let action = self.policy.dispute_expiry_action;
+            let result = match action {
+                ExpiryAction::Resolve => {
+                    self.handle_resolve(Resolve::full(client_id, tx_id, currency, self.clock))
+                }
+                ExpiryAction::Chargeback => {
+                    self.handle_chargeback(Chargeback::full(client_id, tx_id, currency, self.clock))
+                }
+            };
+            if let Err(e) = result {
+                log::warn!("[expiry] dispute of tx={tx_id} could not be settled: {e}");
+                continue;
+            }
+
+            log::debug!(
+                "[expiry] client={client_id} ref_tx={tx_id} amount={amount} {currency} \
+                 settled with {action}"
+            );
+            self.expired_disputes.push(ExpiredDispute::new(
+                client_id, tx_id, currency, amount, action, self.rows, self.clock,
+            ));
         }
     }
 }
@@ -126,6 +739,19 @@ impl PaymentEngine {
     /// increase the available and total funds of the client account."
     ///
     /// Creates the client account if it doesn't exist.
+    /// Deposits into locked accounts are rejected unless the policy allows them,
+    /// deposits into closed or withdraw-only accounts are always rejected.
+    ///
+    /// With a clearing period the deposit enters the pending balance instead and only becomes
+    /// available once enough subsequent rows were seen.
+    /// Clients with a rolling reserve keep part of every deposit in the reserved balance until
+    /// their reserve period ended.
+    /// A configured deposit fee is deducted first and credited to the house account,
+    /// disputes and refunds refer to the net amount the client received. Deposits whose fee
+    /// takes the whole amount are rejected.
+    ///
+    /// Deposits are credited to the client's account in the deposit's currency, which is
+    /// opened with the status of the client's other accounts if it doesn't exist.
     fn handle_deposit(&mut self, deposit: Deposit) -> Result<(), ProcessingError> {
         log::trace!(
             "[deposit] client={} amount={}",
@@ -133,33 +759,99 @@ impl PaymentEngine {
             deposit.amount(),
         );
         let client_id = deposit.client_id();
+        let currency = deposit.currency();
         let amount = deposit.amount();
         let tx_id = deposit.transaction_id();
 
-        let is_new_account = !self.accounts.contains_key(&client_id);
-        let account = self
-            .accounts
-            .entry(client_id)
-            .or_insert_with(|| Account::new(client_id));
+        if let Some(account) = self.accounts.get_or_sibling(client_id, currency) {
+            check_credit(account, &self.policy)?;
+        }
 
-        if is_new_account {
-            log::debug!("[deposit] Created new account for client {client_id} (tx {tx_id})");
+        let fee = self.policy.fees.deposit_fee(client_id, currency, amount);
+        if fee >= amount {
+            return Err(ProcessingError::FeeExceedsAmount {
+                tx: tx_id,
+                amount,
+                fee,
+            });
         }
 
-        if account.is_locked() {
-            return Err(ProcessingError::AccountLocked { client: client_id });
+        let is_new_account = !self.accounts.contains(client_id, currency);
+        let account = self.accounts.get_or_open(client_id, currency, || {
+            new_account(&self.clients, client_id, currency)
+        });
+
+        if is_new_account {
+            log::debug!(
+                "[deposit] Created new {currency} account for client {client_id} (tx {tx_id})"
+            );
         }
 
-        account.deposit(amount);
-        self.deposits.insert(tx_id, deposit);
+        let amount = amount - fee;
+        let deposited_at = deposit.timestamp().or(self.clock);
+        let (reserve, reserve_due) =
+            self.clients
+                .get(&client_id)
+                .map_or((Decimal::ZERO, None), |config| {
+                    let due = DueAt::after(
+                        self.rows,
+                        config.reserve_rows,
+                        deposited_at,
+                        config.reserve_days,
+                    );
+                    (config.reserve_of(amount, currency), Some(due))
+                });
+        let cleared = amount - reserve;
+        let clearing_due = DueAt::after(
+            self.rows,
+            self.policy.deposit_clearing_rows,
+            deposited_at,
+            self.policy.deposit_clearing_days,
+        );
 
+        if clearing_due.is_scheduled() {
+            self.ledger.post(account.deposit_uncleared(cleared));
+        } else {
+            self.ledger.post(account.deposit(cleared));
+        }
+        if !reserve.is_zero() {
+            self.ledger.post(account.deposit_reserved(reserve));
+        }
         log::trace!(
-            "[deposit] client={} tx={} amount={} -> new_balance={}",
+            "[deposit] client={} tx={} amount={} fee={} reserved={} -> new_balance={}",
             client_id,
             tx_id,
             amount,
+            fee,
+            reserve,
             account.available()
         );
+
+        if clearing_due.is_scheduled() && !cleared.is_zero() {
+            self.schedule_release(ScheduledRelease {
+                client_id,
+                currency,
+                amount: cleared,
+                from: ReleaseFrom::Pending,
+                due: clearing_due,
+            });
+        }
+        if !reserve.is_zero() {
+            // Without a reserve period the reserve is released right after the deposit
+            let due = reserve_due.filter(DueAt::is_scheduled).unwrap_or(DueAt {
+                row: Some(self.rows),
+                deadline: None,
+            });
+            self.schedule_release(ScheduledRelease {
+                client_id,
+                currency,
+                amount: reserve,
+                from: ReleaseFrom::Reserve,
+                due,
+            });
+        }
+        self.deposits.insert(tx_id, Tracked::new(deposit, amount));
+        self.collect_fee(fee, currency);
         Ok(())
     }
 
@@ -170,6 +862,11 @@ impl PaymentEngine {
     ///
     /// From spec: "If a client does not have sufficient available funds the withdrawal
     /// should fail and the total amount of funds should not change."
+    ///
+    /// Withdrawals for unknown clients are rejected unless the policy allows creating the
+    /// account on the fly. Withdrawals from locked, closed or deposit-only accounts are rejected.
+    /// A configured withdrawal fee is debited on top of the amount and credited to the house
+    /// account, the client must be able to cover both.
     fn handle_withdrawal(&mut self, withdrawal: Withdrawal) -> Result<(), ProcessingError> {
         log::trace!(
             "[withdrawal] client={} amount={}",
@@ -177,33 +874,217 @@ impl PaymentEngine {
             withdrawal.amount(),
         );
         let client_id = withdrawal.client_id();
+        let currency = withdrawal.currency();
         let amount = withdrawal.amount();
 
-        let account = self
+        let fee = self.policy.fees.withdrawal_fee(client_id, currency, amount);
+        match self.accounts.get(client_id, currency) {
+            Some(account) => check_debit(account, amount + fee)?,
+            None if self.policy.require_account_for_withdrawal => {
+                return Err(ProcessingError::AccountNotFound { client: client_id });
+            }
+            None => {
+                // Check against the account the withdrawal would open, so a rejected
+                // withdrawal leaves no empty account behind
+                let mut account = new_account(&self.clients, client_id, currency);
+                if let Some(sibling) = self.accounts.get_or_sibling(client_id, currency) {
+                    account.inherit_status(sibling);
+                }
+                check_debit(&account, amount + fee)?;
+            }
+        }
+
+        let account = self.accounts.get_or_open(client_id, currency, || {
+            new_account(&self.clients, client_id, currency)
+        });
+        self.ledger.post(account.withdraw(amount + fee));
+        self.withdrawals.insert(
+            withdrawal.transaction_id(),
+            Tracked::new(withdrawal, amount),
+        );
+
+        log::trace!(
+            "[withdrawal] client={} amount={} fee={} -> new_balance={}",
+            client_id,
+            amount,
+            fee,
+            account.available()
+        );
+        self.collect_fee(fee, currency);
+        Ok(())
+    }
+
+    /// Handle a transfer transaction.
+    ///
+    /// Debits the sending client and credits the receiving client atomically: the sender
+    /// must pass the withdrawal rules and the recipient the deposit rules, otherwise neither
+    /// account changes. The recipient's account is created if it doesn't exist.
+    /// Both sides are booked in the transfer's currency.
+    fn handle_transfer(&mut self, transfer: Transfer) -> Result<(), ProcessingError> {
+        let sender_id = transfer.client_id();
+        let recipient_id = transfer.recipient_id();
+        let currency = transfer.currency();
+        let amount = transfer.amount();
+        let tx_id = transfer.transaction_id();
+
+        let sender = self
             .accounts
-            .get_mut(&client_id)
-            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+            .get(sender_id, currency)
+            .ok_or(ProcessingError::AccountNotFound { client: sender_id })?;
+        check_debit(sender, amount)?;
 
-        if account.is_locked() {
-            return Err(ProcessingError::AccountLocked { client: client_id });
+        if let Some(recipient) = self.accounts.get_or_sibling(recipient_id, currency) {
+            check_credit(recipient, &self.policy)?;
         }
 
-        if account.available() < amount {
-            return Err(ProcessingError::InsufficientFunds {
-                client: client_id,
-                available: account.available(),
-                requested: amount,
+        // Both sides are validated, apply them together
+        if let Some(sender) = self.accounts.get_mut(sender_id, currency) {
+            self.ledger.post(sender.withdraw(amount));
+        }
+        let recipient = self.accounts.get_or_open(recipient_id, currency, || {
+            new_account(&self.clients, recipient_id, currency)
+        });
+        self.ledger.post(recipient.deposit(amount));
+        self.transfers.insert(tx_id, Tracked::new(transfer, amount));
+
+        log::trace!(
+            "[transfer] client={sender_id} to={recipient_id} tx={tx_id} amount={amount} {currency}"
+        );
+        Ok(())
+    }
+
+    /// Handle a currency conversion.
+    ///
+    /// Exchanges funds between two accounts of the same client at the rate in effect at the
+    /// row's timestamp, or at the current row without one, less the rate's spread. The source
+    /// account must pass the withdrawal rules and the target account the deposit rules, the target
+    /// account is opened if it doesn't exist. The converted amount is rounded down to the target
+    /// currency's scale, conversions that round down to nothing are rejected.
+    fn handle_convert(&mut self, convert: Convert) -> Result<(), ProcessingError> {
+        let client_id = convert.client_id();
+        let (from, to) = (convert.currency(), convert.target_currency());
+        let amount = convert.amount();
+        let tx_id = convert.transaction_id();
+
+        let rate = self
+            .rates
+            .rate_at(from, to, convert.timestamp(), self.rows)
+            .ok_or(ProcessingError::RateNotFound { from, to })?
+            .clone();
+        let converted = rate.convert(amount);
+        if converted.is_zero() {
+            return Err(ProcessingError::ConversionTooSmall {
+                tx: tx_id,
+                amount,
+                from,
+                to,
             });
         }
 
-        account.withdraw(amount);
+        let source = self
+            .accounts
+            .get(client_id, from)
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+        check_debit(source, amount)?;
+        // The target account shares the status of the source account
+        check_credit(source, &self.policy)?;
+
+        // Both sides are validated, apply them together
+        if let Some(source) = self.accounts.get_mut(client_id, from) {
+            self.ledger.post(source.convert_out(amount));
+        }
+        let target = self
+            .accounts
+            .get_or_open(client_id, to, || new_account(&self.clients, client_id, to));
+        self.ledger.post(target.convert_in(converted));
 
         log::trace!(
-            "[withdrawal] client={} amount={} -> new_balance={}",
-            client_id,
-            amount,
-            account.available()
+            "[convert] client={client_id} tx={tx_id} amount={amount} {from} -> {converted} {to} \
+             rate={} spread={}",
+            rate.rate,
+            rate.spread
         );
+        self.conversions.insert(
+            tx_id,
+            Conversion::new(client_id, self.rows, amount, rate, converted),
+        );
+        Ok(())
+    }
+
+    /// Handle an authorize transaction, the first phase of a two-phase withdrawal.
+    ///
+    /// Follows the withdrawal rules, but only moves the funds from available into the
+    /// authorized balance until a capture or void references the authorization.
+    fn handle_authorize(&mut self, authorize: Authorize) -> Result<(), ProcessingError> {
+        let client_id = authorize.client_id();
+        let amount = authorize.amount();
+        let tx_id = authorize.transaction_id();
+
+        let account = self
+            .accounts
+            .get_mut(client_id, authorize.currency())
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+        check_debit(account, amount)?;
+
+        self.ledger.post(account.authorize(amount));
+        self.authorizations
+            .insert(tx_id, Authorization::new(authorize));
+
+        log::trace!("[authorize] client={client_id} tx={tx_id} authorized={amount}");
+        Ok(())
+    }
+
+    /// Handle a capture transaction.
+    ///
+    /// Finalizes a pending authorization: the authorized funds leave the account.
+    /// Locked accounts keep their authorized withdrawals until they are unlocked or voided.
+    fn handle_capture(&mut self, capture: Capture) -> Result<(), ProcessingError> {
+        let client_id = capture.client_id();
+        let referenced_tx_id = capture.referenced_tx_id();
+
+        let authorization = self.authorization(referenced_tx_id, client_id, capture.currency())?;
+        let amount = authorization.authorize().amount();
+        let currency = authorization.authorize().currency();
+        let next = authorization.settle(AuthorizationState::Captured)?;
+
+        let account = self
+            .accounts
+            .get_mut(client_id, currency)
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+
+        if account.is_locked() {
+            return Err(ProcessingError::AccountLocked { client: client_id });
+        }
+
+        self.ledger.post(account.capture(amount));
+        self.set_authorization_state(referenced_tx_id, next);
+
+        log::trace!("[capture] client={client_id} ref_tx={referenced_tx_id} amount={amount}");
+        Ok(())
+    }
+
+    /// Handle a void transaction.
+    ///
+    /// Cancels a pending authorization: the authorized funds are released back to available.
+    /// Allowed on locked accounts, so failed payouts can always be reversed.
+    fn handle_void(&mut self, void: Void) -> Result<(), ProcessingError> {
+        let client_id = void.client_id();
+        let referenced_tx_id = void.referenced_tx_id();
+
+        let authorization = self.authorization(referenced_tx_id, client_id, void.currency())?;
+        let amount = authorization.authorize().amount();
+        let currency = authorization.authorize().currency();
+        let next = authorization.settle(AuthorizationState::Voided)?;
+
+        let account = self
+            .accounts
+            .get_mut(client_id, currency)
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+
+        self.ledger.post(account.void(amount));
+        self.set_authorization_state(referenced_tx_id, next);
+
+        log::trace!("[void] client={client_id} ref_tx={referenced_tx_id} released={amount}");
         Ok(())
     }
 
@@ -217,6 +1098,17 @@ impl PaymentEngine {
     ///
     /// From spec: "If the tx specified by the dispute doesn't exist you can ignore it and
     /// assume this is an error on our partner's side."
+    ///
+    /// Unless the policy allows it, a dispute that would drive available funds negative fails.
+    ///
+    /// Disputing a withdrawal (if enabled by policy) provisionally credits the withdrawn
+    /// amount back into held funds, increasing held and total funds.
+    ///
+    /// Disputing a transfer is done by the sending client and holds the funds in the
+    /// receiving client's account.
+    ///
+    /// A dispute may carry a partial amount up to the undisputed part of the transaction,
+    /// without an amount the whole undisputed part is disputed.
     fn handle_dispute(&mut self, dispute: Dispute) -> Result<(), ProcessingError> {
         log::trace!(
             "[dispute] client={} ref_tx={}",
@@ -226,39 +1118,66 @@ impl PaymentEngine {
         let client_id = dispute.client_id();
         let referenced_tx_id = dispute.referenced_tx_id();
 
-        let deposit = self.deposits.get_mut(&referenced_tx_id).ok_or(
-            ProcessingError::TransactionNotFound {
-                tx: referenced_tx_id,
-            },
+        let target = self.dispute_target(
+            referenced_tx_id,
+            client_id,
+            dispute.currency(),
+            dispute.amount(),
         )?;
-
-        if deposit.client_id() != client_id {
-            return Err(ProcessingError::ClientMismatch {
-                tx: referenced_tx_id,
-                expected: deposit.client_id(),
-                got: client_id,
-            });
-        }
-
-        if self.disputes.contains(&referenced_tx_id) {
-            return Err(ProcessingError::AlreadyUnderDispute {
-                tx: referenced_tx_id,
-            });
+        let disputed_at = dispute.timestamp().or(self.clock);
+        let window = self.policy.dispute_window_days;
+        if let (Some(applied_at), Some(disputed_at)) = (target.timestamp, disputed_at) {
+            if window > 0 && disputed_at > applied_at.add_days(window) {
+                return Err(ProcessingError::DisputeWindowClosed {
+                    tx: referenced_tx_id,
+                    days: window,
+                });
+            }
         }
+        let (next, amount) = target.lifecycle.dispute(
+            referenced_tx_id,
+            dispute.amount(),
+            self.policy.allow_redispute,
+        )?;
 
-        let amount = deposit.amount();
-
+        let holder_id = target.holder;
         let account = self
             .accounts
-            .get_mut(&client_id)
-            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+            .get_mut(holder_id, target.currency)
+            .ok_or(ProcessingError::AccountNotFound { client: holder_id })?;
+
+        if account.is_closed() {
+            return Err(ProcessingError::AccountClosed { client: holder_id });
+        }
 
         if account.is_locked() {
-            return Err(ProcessingError::AccountLocked { client: client_id });
+            return Err(ProcessingError::AccountLocked { client: holder_id });
         }
 
-        self.disputes.insert(referenced_tx_id);
-        account.hold(amount);
+        if !matches!(target.direction, Direction::Debit)
+            && !self.policy.allow_negative_available_on_dispute
+            && account.available() < amount
+        {
+            // Disputes never draw on the credit limit
+            return Err(ProcessingError::InsufficientFunds {
+                client: holder_id,
+                available: account.available(),
+                limit: Decimal::ZERO,
+                requested: amount,
+            });
+        }
+
+        let entry = match target.direction {
+            Direction::Credit | Direction::Transfer => account.hold(amount),
+            Direction::Debit => account.hold_reversal(amount),
+        };
+        self.ledger.post(entry);
+        self.set_lifecycle(referenced_tx_id, next);
+
+        // The expiry window starts when the dispute opens, partial disputes don't extend it
+        if target.lifecycle.state() != TransactionState::Disputed {
+            self.schedule_expiry(referenced_tx_id, disputed_at);
+        }
 
         log::trace!("[dispute] client={client_id} ref_tx={referenced_tx_id} held={amount}");
         Ok(())
@@ -274,6 +1193,13 @@ impl PaymentEngine {
     ///
     /// From spec: "If the tx specified doesn't exist, or the tx isn't under dispute, you
     /// can ignore the resolve and assume this is an error on our partner's side."
+    ///
+    /// Resolving a withdrawal dispute upholds the withdrawal: the provisional credit is
+    /// removed from held and total funds again.
+    ///
+    /// A resolve may carry a partial amount, without an amount everything disputed is released.
+    ///
+    /// Open disputes are still settled on locked accounts, so held funds can be released.
     fn handle_resolve(&mut self, resolve: Resolve) -> Result<(), ProcessingError> {
         log::trace!(
             "[resolve] client={} ref_tx={}",
@@ -283,39 +1209,30 @@ impl PaymentEngine {
         let client_id = resolve.client_id();
         let referenced_tx_id = resolve.referenced_tx_id();
 
-        let deposit = self.deposits.get_mut(&referenced_tx_id).ok_or(
-            ProcessingError::TransactionNotFound {
-                tx: referenced_tx_id,
-            },
+        let target = self.dispute_target(
+            referenced_tx_id,
+            client_id,
+            resolve.currency(),
+            resolve.amount(),
         )?;
-
-        if deposit.client_id() != client_id {
-            return Err(ProcessingError::ClientMismatch {
-                tx: referenced_tx_id,
-                expected: deposit.client_id(),
-                got: client_id,
-            });
-        }
-
-        if !self.disputes.contains(&referenced_tx_id) {
-            return Err(ProcessingError::NotUnderDispute {
-                tx: referenced_tx_id,
-            });
-        }
-
-        let amount = deposit.amount();
+        let (next, amount) = target
+            .lifecycle
+            .resolve(referenced_tx_id, resolve.amount())?;
 
         let account = self
             .accounts
-            .get_mut(&client_id)
-            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
-
-        if account.is_locked() {
-            return Err(ProcessingError::AccountLocked { client: client_id });
-        }
+            .get_mut(target.holder, target.currency)
+            .ok_or(ProcessingError::AccountNotFound {
+                client: target.holder,
+            })?;
 
-        self.disputes.remove(&referenced_tx_id);
-        account.release(amount);
+        let entry = match target.direction {
+            Direction::Credit | Direction::Transfer => account.release(amount),
+            Direction::Debit => account.cancel_reversal(amount),
+        };
+        self.ledger.post(entry);
+        self.end_expiry(referenced_tx_id, &next);
+        self.set_lifecycle(referenced_tx_id, next);
 
         log::trace!("[resolve] client={client_id} ref_tx={referenced_tx_id} released={amount}");
         Ok(())
@@ -328,8 +1245,24 @@ impl PaymentEngine {
     /// that the clients held funds and total funds should decrease by the amount previously
     /// disputed. If a chargeback occurs the client's account should be immediately frozen."
     ///
+    /// Freezing the account can be turned off by policy.
+    ///
     /// From spec: "If the tx specified doesn't exist, or the tx isn't under dispute, you
     /// can ignore chargeback and assume this is an error on our partner's side."
+    ///
+    /// Charging back a withdrawal reverses the debit: the held funds are credited back to
+    /// available funds instead of being removed, and the account is frozen as well.
+    ///
+    /// Charging back a transfer moves the held funds from the receiving client back to the
+    /// sending client in one entry. The recipient is not frozen, the funds never left the
+    /// engine's clients, and available funds the recipient already spent stay negative and
+    /// block further debits. The chargeback is rejected when the sending client would not
+    /// accept a deposit, e.g. once it is closed.
+    ///
+    /// A chargeback may carry a partial amount, without an amount everything disputed is
+    /// charged back.
+    ///
+    /// Open disputes are still settled on locked accounts, so held funds can reach zero.
     fn handle_chargeback(&mut self, chargeback: Chargeback) -> Result<(), ProcessingError> {
         log::trace!(
             "[chargeback] client={} ref_tx={}",
@@ -339,44 +1272,672 @@ impl PaymentEngine {
         let client_id = chargeback.client_id();
         let referenced_tx_id = chargeback.referenced_tx_id();
 
-        let deposit = self.deposits.get_mut(&referenced_tx_id).ok_or(
-            ProcessingError::TransactionNotFound {
-                tx: referenced_tx_id,
+        let target = self.dispute_target(
+            referenced_tx_id,
+            client_id,
+            chargeback.currency(),
+            chargeback.amount(),
+        )?;
+        let currency = target.currency;
+        let (next, amount) = target
+            .lifecycle
+            .chargeback(referenced_tx_id, chargeback.amount())?;
+
+        // The sender is credited like a deposit, so it must still accept one
+        let transfer = matches!(target.direction, Direction::Transfer);
+        if transfer {
+            let sender = self
+                .accounts
+                .get(client_id, currency)
+                .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+            check_credit(sender, &self.policy)?;
+        }
+
+        let account = self.accounts.get_mut(target.holder, currency).ok_or(
+            ProcessingError::AccountNotFound {
+                client: target.holder,
             },
         )?;
 
-        if deposit.client_id() != client_id {
+        // A transfer chargeback reverses a movement between two clients, not a payment
+        // from outside, so the recipient isn't locked
+        let lock = self.policy.lock_on_chargeback && !transfer;
+        let entry = match target.direction {
+            Direction::Credit => account.chargeback(amount, lock),
+            Direction::Debit => account.chargeback_reversal(amount, lock),
+            Direction::Transfer => account.chargeback_transfer(client_id, amount),
+        };
+        if transfer {
+            if let Some(sender) = self.accounts.get_mut(client_id, currency) {
+                sender.apply(&entry);
+            }
+        }
+        self.ledger.post(entry);
+        if lock {
+            // The lock applies to every currency of the client
+            let accounts = self.accounts.client_mut(target.holder);
+            for account in accounts.into_iter().flat_map(BTreeMap::values_mut) {
+                account.freeze(FreezeReason::Chargeback);
+            }
+        }
+        self.end_expiry(referenced_tx_id, &next);
+        self.set_lifecycle(referenced_tx_id, next);
+
+        log::trace!(
+            "[chargeback] client={client_id} ref_tx={referenced_tx_id} amount={amount} locked={}",
+            self.policy.lock_on_chargeback
+        );
+        Ok(())
+    }
+
+    /// Handle a refund transaction.
+    ///
+    /// Returns part or all of a deposit to the payer, debiting the client's available and
+    /// total funds like a withdrawal. Cumulative refunds never exceed the deposited amount,
+    /// and only the part that is neither disputed nor charged back can be refunded.
+    /// A refunded part can no longer be disputed.
+    ///
+    /// A refund may carry a partial amount, without an amount everything refundable is refunded.
+    fn handle_refund(&mut self, refund: Refund) -> Result<(), ProcessingError> {
+        let client_id = refund.client_id();
+        let referenced_tx_id = refund.referenced_tx_id();
+
+        let deposit = self
+            .deposits
+            .get(&referenced_tx_id)
+            .ok_or_else(|| self.missing_target(referenced_tx_id))?;
+
+        let owner = deposit.transaction().client_id();
+        if owner != client_id {
             return Err(ProcessingError::ClientMismatch {
                 tx: referenced_tx_id,
-                expected: deposit.client_id(),
+                expected: owner,
                 got: client_id,
             });
         }
+        let currency = deposit.transaction().currency();
+        check_currency(
+            referenced_tx_id,
+            currency,
+            refund.currency(),
+            refund.amount(),
+        )?;
 
-        if !self.disputes.contains(&referenced_tx_id) {
-            return Err(ProcessingError::NotUnderDispute {
-                tx: referenced_tx_id,
-            });
+        let (next, amount) = deposit
+            .lifecycle()
+            .refund(referenced_tx_id, refund.amount())?;
+
+        let account = self
+            .accounts
+            .get_mut(client_id, currency)
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+        check_debit(account, amount)?;
+
+        self.ledger.post(account.withdraw(amount));
+        self.set_lifecycle(referenced_tx_id, next);
+
+        log::trace!("[refund] client={client_id} ref_tx={referenced_tx_id} refunded={amount}");
+        Ok(())
+    }
+}
+
+/// Ensure an account accepts incoming funds (deposits, transfers to it).
+///
+/// Closed and withdraw-only accounts never accept funds,
+/// locked accounts only if the policy allows deposits on locked accounts.
+fn check_credit(account: &Account, policy: &EnginePolicy) -> Result<(), ProcessingError> {
+    let client = account.client_id();
+
+    if account.is_closed() {
+        return Err(ProcessingError::AccountClosed { client });
+    }
+
+    if account.is_locked() && policy.reject_deposits_on_locked {
+        return Err(ProcessingError::AccountLocked { client });
+    }
+
+    if account.restriction() == Restriction::WithdrawOnly {
+        return Err(ProcessingError::AccountRestricted {
+            client,
+            status: account.status(),
+        });
+    }
+    Ok(())
+}
+
+/// Ensure `amount` can leave an account (withdrawals, transfers from it).
+///
+/// Available funds may go down to the negative of the account's credit limit.
+fn check_debit(account: &Account, amount: Decimal) -> Result<(), ProcessingError> {
+    let client = account.client_id();
+
+    if account.is_closed() {
+        return Err(ProcessingError::AccountClosed { client });
+    }
+
+    if account.is_locked() {
+        return Err(ProcessingError::AccountLocked { client });
+    }
+
+    if account.restriction() == Restriction::DepositOnly {
+        return Err(ProcessingError::AccountRestricted {
+            client,
+            status: account.status(),
+        });
+    }
+
+    check_funds(account, amount)
+}
+
+/// Ensure `amount` fits into the account's headroom, its available funds plus credit limit.
+fn check_funds(account: &Account, amount: Decimal) -> Result<(), ProcessingError> {
+    if account.headroom() < amount {
+        return Err(ProcessingError::InsufficientFunds {
+            client: account.client_id(),
+            available: account.available(),
+            limit: account.credit_limit(),
+            requested: amount,
+        });
+    }
+    Ok(())
+}
+
+/// Ensure a row referencing transaction `tx` is in the currency of that transaction.
+///
+/// A row without a currency is booked in `expected`, an explicit currency must match it.
+/// A partial `amount` must fit the scale of `expected`.
+fn check_currency(
+    tx: TransactionId,
+    expected: Currency,
+    got: Option<Currency>,
+    amount: Option<Decimal>,
+) -> Result<(), ProcessingError> {
+    if let Some(got) = got.filter(|got| *got != expected) {
+        return Err(ProcessingError::CurrencyMismatch { tx, expected, got });
+    }
+    if let Some(amount) = amount.filter(|amount| amount.scale() > expected.scale()) {
+        return Err(ProcessingError::AmountTooPrecise {
+            tx,
+            amount,
+            currency: expected,
+        });
+    }
+    Ok(())
+}
+
+/// Create an account in `currency`, with the credit limit configured for the client if it is
+/// the default currency.
+fn new_account(
+    clients: &HashMap<ClientId, ClientConfig>,
+    client_id: ClientId,
+    currency: Currency,
+) -> Account {
+    let mut account = Account::new(client_id, currency);
+    if let Some(config) = clients
+        .get(&client_id)
+        .filter(|_| currency == Currency::default())
+    {
+        account.set_credit_limit(config.credit_limit);
+    }
+    account
+}
+
+// =============================================================================
+// Administrative Handlers
+// =============================================================================
+
+impl PaymentEngine {
+    /// Handle a freeze transaction.
+    ///
+    /// Compliance proactively freezes the client's accounts in every currency. Freezing an
+    /// account that is already frozen is allowed, so the action is recorded either way.
+    fn handle_freeze(&mut self, freeze: Freeze) -> Result<(), ProcessingError> {
+        let client_id = freeze.client_id();
+        let accounts = self.open_accounts_mut(client_id)?;
+
+        for account in accounts.values_mut() {
+            account.freeze(FreezeReason::Compliance);
+            log::debug!(
+                "[freeze] client={client_id} tx={} -> {} account {}",
+                freeze.transaction_id(),
+                account.currency(),
+                account.status()
+            );
         }
+        Ok(())
+    }
 
-        let amount = deposit.amount();
+    /// Handle an unfreeze transaction.
+    ///
+    /// Compliance lifts its own freeze, a chargeback lock stays in place.
+    fn handle_unfreeze(&mut self, unfreeze: Unfreeze) -> Result<(), ProcessingError> {
+        let client_id = unfreeze.client_id();
+        self.lift_freeze(client_id, FreezeReason::Compliance)?;
+
+        log::debug!(
+            "[unfreeze] client={client_id} tx={} -> compliance freeze lifted",
+            unfreeze.transaction_id()
+        );
+        Ok(())
+    }
+
+    /// Handle an unlock transaction.
+    ///
+    /// Compliance lifts the chargeback lock of an account after review,
+    /// a compliance freeze stays in place.
+    fn handle_unlock(&mut self, unlock: Unlock) -> Result<(), ProcessingError> {
+        let client_id = unlock.client_id();
+        self.lift_freeze(client_id, FreezeReason::Chargeback)?;
+
+        log::debug!(
+            "[unlock] client={client_id} tx={} -> chargeback lock lifted",
+            unlock.transaction_id()
+        );
+        Ok(())
+    }
+
+    /// Handle a restrict transaction (`withdraw_only`, `deposit_only` or `activate`).
+    ///
+    /// Restrictions apply to the client's accounts in every currency while they are not
+    /// frozen, freezes are left untouched.
+    fn handle_restrict(&mut self, restrict: Restrict) -> Result<(), ProcessingError> {
+        let client_id = restrict.client_id();
+        let accounts = self.open_accounts_mut(client_id)?;
 
+        for account in accounts.values_mut() {
+            account.restrict(restrict.restriction());
+            log::debug!(
+                "[restrict] client={client_id} tx={} -> {} account {}",
+                restrict.transaction_id(),
+                account.currency(),
+                account.status()
+            );
+        }
+        Ok(())
+    }
+
+    /// Handle an adjustment transaction.
+    ///
+    /// An operator credits (positive amount) or debits (negative amount) the client's balance
+    /// with a reason code. Adjustments follow the deposit and withdrawal rules, unless the
+    /// policy allows them on frozen and restricted accounts. Closed accounts and debits beyond
+    /// the available funds and credit limit are always rejected.
+    fn handle_adjustment(&mut self, adjustment: Adjustment) -> Result<(), ProcessingError> {
+        let client_id = adjustment.client_id();
+        let amount = adjustment.amount();
         let account = self
             .accounts
-            .get_mut(&client_id)
+            .get_mut(client_id, adjustment.currency())
             .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
 
-        // Sanity check
-        if account.is_locked() {
-            return Err(ProcessingError::AccountLocked { client: client_id });
+        if account.is_closed() {
+            return Err(ProcessingError::AccountClosed { client: client_id });
         }
 
-        self.disputes.remove(&referenced_tx_id);
-        account.chargeback(amount);
+        if self.policy.allow_adjustments_on_restricted {
+            if amount.is_sign_negative() {
+                check_funds(account, -amount)?;
+            }
+        } else if amount.is_sign_positive() {
+            check_credit(account, &self.policy)?;
+        } else {
+            check_debit(account, -amount)?;
+        }
 
-        log::trace!(
-            "[chargeback] client={client_id} ref_tx={referenced_tx_id} amount={amount} -> account LOCKED"
+        self.ledger.post(account.adjust(amount));
+
+        log::debug!(
+            "[adjustment] client={client_id} tx={} amount={amount} reason={}",
+            adjustment.transaction_id(),
+            adjustment.reason()
         );
+        self.adjustments
+            .insert(adjustment.transaction_id(), adjustment);
+        Ok(())
+    }
+
+    /// Handle a credit limit transaction.
+    ///
+    /// An operator sets the overdraft of the client's account in the row's currency, replacing
+    /// the limit from the policy. Lowering the limit below the current overdraft is allowed,
+    /// later debits are rejected until the account is back within the limit.
+    fn handle_credit_limit(&mut self, credit_limit: CreditLimit) -> Result<(), ProcessingError> {
+        let client_id = credit_limit.client_id();
+        let account = self
+            .open_accounts_mut(client_id)?
+            .get_mut(&credit_limit.currency())
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+
+        account.set_credit_limit(credit_limit.limit());
+
+        log::debug!(
+            "[credit_limit] client={client_id} tx={} limit={} -> headroom {}",
+            credit_limit.transaction_id(),
+            credit_limit.limit(),
+            account.headroom()
+        );
+        Ok(())
+    }
+
+    /// Handle a close transaction.
+    ///
+    /// Only clients without any funds (available, held and total all zero in every currency)
+    /// can be closed. Closed accounts reject every later transaction.
+    fn handle_close(&mut self, close: Close) -> Result<(), ProcessingError> {
+        let client_id = close.client_id();
+        let accounts = self.open_accounts_mut(client_id)?;
+
+        if let Some(account) = accounts.values().find(|account| !account.is_empty()) {
+            return Err(ProcessingError::AccountNotEmpty {
+                client: client_id,
+                available: account.available(),
+                held: account.held(),
+            });
+        }
+
+        for account in accounts.values_mut() {
+            account.close();
+        }
+
+        log::debug!(
+            "[close] client={client_id} tx={} -> account CLOSED",
+            close.transaction_id()
+        );
+        Ok(())
+    }
+
+    /// Lift one freeze reason of an existing, open client in every currency.
+    fn lift_freeze(
+        &mut self,
+        client_id: ClientId,
+        reason: FreezeReason,
+    ) -> Result<(), ProcessingError> {
+        let accounts = self.open_accounts_mut(client_id)?;
+
+        if !accounts
+            .values()
+            .all(|account| account.is_frozen_for(reason))
+        {
+            return Err(ProcessingError::AccountNotFrozen {
+                client: client_id,
+                reason,
+            });
+        }
+
+        for account in accounts.values_mut() {
+            account.unfreeze(reason);
+        }
         Ok(())
     }
+
+    /// Look up the accounts of an existing client that was not closed, by currency.
+    fn open_accounts_mut(
+        &mut self,
+        client_id: ClientId,
+    ) -> Result<&mut BTreeMap<Currency, Account>, ProcessingError> {
+        let accounts = self
+            .accounts
+            .client_mut(client_id)
+            .ok_or(ProcessingError::AccountNotFound { client: client_id })?;
+
+        // All accounts of a client share their status
+        if accounts.values().any(Account::is_closed) {
+            return Err(ProcessingError::AccountClosed { client: client_id });
+        }
+        Ok(accounts)
+    }
+}
+
+// =============================================================================
+// Referenced Transaction Lookups
+// =============================================================================
+
+/// Whether a retained transaction credited or debited the client's account,
+/// or moved funds from the client to another client
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Credit,
+    Debit,
+    Transfer,
+}
+
+/// Snapshot of a retained transaction referenced by a dispute, resolve or chargeback
+#[derive(Debug, Clone, Copy)]
+struct DisputeTarget {
+    direction: Direction,
+    /// Client whose funds are held while the transaction is disputed
+    holder: ClientId,
+    /// Currency of the transaction, rows referencing it are booked in it
+    currency: Currency,
+    /// When the transaction happened, if the input carries timestamps
+    timestamp: Option<Timestamp>,
+    lifecycle: Lifecycle,
+}
+
+impl PaymentEngine {
+    /// Error for a referenced transaction that can't be used by the row: `IneligibleTransaction`
+    /// if it was applied but is of a type or excluded by a policy the row doesn't allow,
+    /// `TransactionNotFound` if it was never seen, which is the only case worth parking for.
+    fn missing_target(&self, tx_id: TransactionId) -> ProcessingError {
+        if self.applied.contains_key(&tx_id) {
+            ProcessingError::IneligibleTransaction { tx: tx_id }
+        } else {
+            ProcessingError::TransactionNotFound { tx: tx_id }
+        }
+    }
+
+    /// Look up the transaction referenced by a dispute/resolve/chargeback and ensure it
+    /// belongs to `client_id` and is in `currency`, see `check_currency`.
+    ///
+    /// Withdrawals are only found if `EnginePolicy::allow_withdrawal_disputes` is set.
+    /// Transfers are owned by the sending client.
+    fn dispute_target(
+        &self,
+        tx_id: TransactionId,
+        client_id: ClientId,
+        currency: Option<Currency>,
+        amount: Option<Decimal>,
+    ) -> Result<DisputeTarget, ProcessingError> {
+        let (owner, target) = if let Some(deposit) = self.deposits.get(&tx_id) {
+            let owner = deposit.transaction().client_id();
+            let target = DisputeTarget {
+                direction: Direction::Credit,
+                holder: owner,
+                currency: deposit.transaction().currency(),
+                timestamp: deposit.transaction().timestamp(),
+                lifecycle: deposit.lifecycle(),
+            };
+            (owner, target)
+        } else if let Some(withdrawal) = self
+            .withdrawals
+            .get(&tx_id)
+            .filter(|_| self.policy.allow_withdrawal_disputes)
+        {
+            let owner = withdrawal.transaction().client_id();
+            let target = DisputeTarget {
+                direction: Direction::Debit,
+                holder: owner,
+                currency: withdrawal.transaction().currency(),
+                timestamp: withdrawal.transaction().timestamp(),
+                lifecycle: withdrawal.lifecycle(),
+            };
+            (owner, target)
+        } else if let Some(transfer) = self.transfers.get(&tx_id) {
+            let target = DisputeTarget {
+                direction: Direction::Transfer,
+                holder: transfer.transaction().recipient_id(),
+                currency: transfer.transaction().currency(),
+                timestamp: transfer.transaction().timestamp(),
+                lifecycle: transfer.lifecycle(),
+            };
+            (transfer.transaction().client_id(), target)
+        } else {
+            return Err(self.missing_target(tx_id));
+        };
+
+        if owner != client_id {
+            return Err(ProcessingError::ClientMismatch {
+                tx: tx_id,
+                expected: owner,
+                got: client_id,
+            });
+        }
+        check_currency(tx_id, target.currency, currency, amount)?;
+        Ok(target)
+    }
+
+    /// Returns the owner and currency of a retained deposit, withdrawal or transfer.
+    fn retained_owner(&self, tx_id: TransactionId) -> Option<(ClientId, Currency)> {
+        self.deposits
+            .get(&tx_id)
+            .map(|deposit| {
+                (
+                    deposit.transaction().client_id(),
+                    deposit.transaction().currency(),
+                )
+            })
+            .or_else(|| {
+                self.withdrawals.get(&tx_id).map(|withdrawal| {
+                    (
+                        withdrawal.transaction().client_id(),
+                        withdrawal.transaction().currency(),
+                    )
+                })
+            })
+            .or_else(|| {
+                self.transfers.get(&tx_id).map(|transfer| {
+                    (
+                        transfer.transaction().client_id(),
+                        transfer.transaction().currency(),
+                    )
+                })
+            })
+    }
+
+    /// Look up the authorization referenced by a capture/void and ensure it belongs to
+    /// `client_id` and is in `currency`, see `check_currency`.
+    fn authorization(
+        &self,
+        tx_id: TransactionId,
+        client_id: ClientId,
+        currency: Option<Currency>,
+    ) -> Result<&Authorization, ProcessingError> {
+        let authorization = self
+            .authorizations
+            .get(&tx_id)
+            .ok_or_else(|| self.missing_target(tx_id))?;
+
+        let owner = authorization.authorize().client_id();
+        if owner != client_id {
+            return Err(ProcessingError::ClientMismatch {
+                tx: tx_id,
+                expected: owner,
+                got: client_id,
+            });
+        }
+        check_currency(tx_id, authorization.authorize().currency(), currency, None)?;
+        Ok(authorization)
+    }
+
+    /// Update the settlement state of an authorized withdrawal.
+    fn set_authorization_state(&mut self, tx_id: TransactionId, state: AuthorizationState) {
+        if let Some(authorization) = self.authorizations.get_mut(&tx_id) {
+            authorization.set_state(state);
+        }
+    }
+
+    /// Update the dispute lifecycle of a retained transaction.
+    fn set_lifecycle(&mut self, tx_id: TransactionId, lifecycle: Lifecycle) {
+        if let Some(deposit) = self.deposits.get_mut(&tx_id) {
+            deposit.set_lifecycle(lifecycle);
+        } else if let Some(withdrawal) = self.withdrawals.get_mut(&tx_id) {
+            withdrawal.set_lifecycle(lifecycle);
+        } else if let Some(transfer) = self.transfers.get_mut(&tx_id) {
+            transfer.set_lifecycle(lifecycle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ledger::JournalEntry;
+    use rust_decimal_macros::dec;
+    use std::io::Cursor;
+
+    fn stray_entry(client_id: ClientId) -> JournalEntry {
+        JournalEntry::transfer(
+            Currency::default(),
+            LedgerAccount::Settlement,
+            LedgerAccount::Client(client_id, Bucket::Available),
+            dec!(5),
+        )
+    }
+
+    #[test]
+    fn test_trial_balance_detects_unapplied_entry() {
+        let mut engine = PaymentEngine::new();
+        engine
+            .process_transactions(Cursor::new("type,client,tx,amount\ndeposit,1,1,100.0"))
+            .unwrap();
+        assert!(engine.check_trial_balance().is_ok());
+
+        // Balanced, but the account never saw it
+        engine.ledger.post(stray_entry(1));
+        assert!(engine.ledger.is_balanced());
+        assert!(engine.check_trial_balance().is_err());
+    }
+
+    #[test]
+    fn test_settled_disputes_stop_their_expiry() {
+        let policy = EnginePolicy {
+            dispute_expiry_rows: 100,
+            ..EnginePolicy::default()
+        };
+        let mut engine = PaymentEngine::with_policy(policy);
+        let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,100.0
+dispute,1,1,
+dispute,1,2,
+resolve,1,1,40.0";
+        engine.process_transactions(Cursor::new(input)).unwrap();
+        assert_eq!(engine.expiry_windows.len(), 2);
+        assert_eq!(engine.expiries.len(), 2);
+
+        let input = "type,client,tx,amount
+resolve,1,1,
+chargeback,1,2,";
+        engine.process_transactions(Cursor::new(input)).unwrap();
+        assert!(engine.expiry_windows.is_empty());
+        assert!(engine.expiries.is_empty());
+    }
+
+    #[test]
+    fn test_settled_disputes_leave_the_deadline_queue() {
+        let policy = EnginePolicy {
+            dispute_expiry_days: 30,
+            ..EnginePolicy::default()
+        };
+        let mut engine = PaymentEngine::with_policy(policy);
+        let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+dispute,1,1,,2024-01-02T00:00:00Z";
+        engine.process_transactions(Cursor::new(input)).unwrap();
+        assert_eq!(engine.expiry_deadlines.len(), 1);
+
+        // The clock doesn't advance, the deadline would never come due
+        let input = "type,client,tx,amount,timestamp
+resolve,1,1,,2024-01-02T00:00:00Z";
+        engine.process_transactions(Cursor::new(input)).unwrap();
+        assert!(engine.expiry_deadlines.is_empty());
+        assert!(engine.expiry_windows.is_empty());
+    }
+
+    #[test]
+    fn test_trial_balance_detects_funds_without_account() {
+        let mut engine = PaymentEngine::new();
+        engine.ledger.post(stray_entry(2));
+        assert!(engine.check_trial_balance().is_err());
+    }
 }
@@ -0,0 +1,338 @@
+use serde::Deserialize;
+
+use super::account::ClientId;
+use super::currency::Currency;
+use super::error::Error;
+use super::fees::FeeSchedule;
+use super::Decimal;
+
+/// Configurable business rules of the `PaymentEngine`.
+///
+/// The defaults reproduce the engine's original behavior.
+/// Policies can be loaded from TOML, every field is optional:
+///
+/// ```toml
+/// idempotent_replays = true
+/// lock_on_chargeback = false
+///
+/// [[clients]]
+/// client = 7
+/// reserve_percentage = 10
+/// reserve_rows = 1000
+/// credit_limit = 250
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct EnginePolicy {
+    /// Treat an exact replay of an already applied transaction as an idempotent no-op instead
+    /// of rejecting it as a duplicate. A replay repeats every column of the row: type, client,
+    /// tx, amount, currency, timestamp and the type-specific `to`, `reason` and `to_currency`.
+    pub idempotent_replays: bool,
+    /// Allow the resolved part of a transaction to be disputed again. The part that was never
+    /// disputed can always be disputed.
+    pub allow_redispute: bool,
+    /// Allow withdrawals to be disputed, resolved and charged back.
+    /// A withdrawal chargeback credits the withdrawn funds back to the client.
+    pub allow_withdrawal_disputes: bool,
+    /// Allow a dispute to hold more than the available funds, driving available negative.
+    /// Otherwise such a dispute fails with insufficient funds.
+    pub allow_negative_available_on_dispute: bool,
+    /// Reject withdrawals for clients without an account.
+    /// Otherwise the account is created like on a deposit.
+    pub require_account_for_withdrawal: bool,
+    /// Lock the client's account when a chargeback occurs, transfer chargebacks never lock.
+    pub lock_on_chargeback: bool,
+    /// Reject deposits into locked accounts.
+    pub reject_deposits_on_locked: bool,
+    /// Apply operator adjustments on frozen and restricted (`withdraw_only`, `deposit_only`)
+    /// accounts. Otherwise adjustments follow the deposit and withdrawal rules.
+    pub allow_adjustments_on_restricted: bool,
+    /// Number of subsequent rows after which a deposit clears. Until then the deposit sits in
+    /// the pending balance and can't be withdrawn. `0` clears deposits immediately, unless
+    /// `deposit_clearing_days` is set.
+    pub deposit_clearing_rows: u64,
+    /// Number of days after which a deposit clears, measured by the `timestamp` column.
+    /// Deposits clear after this or `deposit_clearing_rows`, whichever ends first. `0` or
+    /// deposits without a timestamp before the first timestamped row disable the time window.
+    pub deposit_clearing_days: u64,
+    /// Number of subsequent rows after which an open dispute expires and is settled with
+    /// `dispute_expiry_action`. `0` keeps disputes open until they are resolved or charged back.
+    pub dispute_expiry_rows: u64,
+    /// Number of days after which an open dispute expires, measured by the `timestamp` column.
+    /// Disputes expire after this or `dispute_expiry_rows`, whichever ends first. `0` disables
+    /// the time window.
+    pub dispute_expiry_days: u64,
+    /// How disputes are settled when they expire.
+    pub dispute_expiry_action: ExpiryAction,
+    /// Number of days after a transaction within which it can be disputed, measured by the
+    /// `timestamp` column. `0` or rows without timestamps allow disputes at any time.
+    pub dispute_window_days: u64,
+    /// Number of subsequent rows a dispute, resolve, chargeback, refund, capture or void waits
+    /// for the transaction it references if that has not arrived yet. `0` rejects such rows
+    /// right away.
+    pub reorder_window_rows: u64,
+    /// Maximum number of rows waiting for the transaction they reference, the oldest row is
+    /// dropped once the buffer is full.
+    pub reorder_buffer_size: usize,
+    /// Per-client settings, e.g. rolling reserves for high-risk clients and credit limits.
+    pub clients: Vec<ClientConfig>,
+    /// Fees charged on deposits and withdrawals, none by default.
+    pub fees: FeeSchedule,
+}
+
+/// Settlement of a dispute that was neither resolved nor charged back within the dispute
+/// window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryAction {
+    /// Release the held funds to the client
+    #[default]
+    Resolve,
+    /// Charge the held funds back, locking the account if `lock_on_chargeback` is set
+    Chargeback,
+}
+
+impl std::fmt::Display for ExpiryAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpiryAction::Resolve => write!(f, "resolve"),
+            ExpiryAction::Chargeback => write!(f, "chargeback"),
+        }
+    }
+}
+
+/// Settings of a single client, configured in the policy's `[[clients]]` tables.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    /// The configured client
+    pub client: ClientId,
+    /// Percentage (0 to 100) of every deposit that is kept in the reserve balance.
+    pub reserve_percentage: Decimal,
+    /// Number of subsequent rows after which reserved funds are released to available funds.
+    /// `0` releases them right away, unless `reserve_days` is set.
+    pub reserve_rows: u64,
+    /// Number of days after which reserved funds are released, measured by the `timestamp`
+    /// column. Reserves are released after this or `reserve_rows`, whichever ends first. `0`
+    /// disables the time window.
+    pub reserve_days: u64,
+    /// Credit limit the client's default currency account is opened with, debits may take
+    /// available funds down to `-credit_limit`.
+    pub credit_limit: Decimal,
+}
+
+impl ClientConfig {
+    /// Returns the part of a deposit that goes into the reserve balance, rounded to the
+    /// decimal places of its currency
+    pub fn reserve_of(&self, amount: Decimal, currency: Currency) -> Decimal {
+        (amount * self.reserve_percentage / Decimal::ONE_HUNDRED).round_dp(currency.scale())
+    }
+}
+
+impl Default for EnginePolicy {
+    fn default() -> Self {
+        Self {
+            idempotent_replays: false,
+            allow_redispute: true,
+            allow_withdrawal_disputes: false,
+            allow_negative_available_on_dispute: true,
+            require_account_for_withdrawal: true,
+            lock_on_chargeback: true,
+            reject_deposits_on_locked: true,
+            allow_adjustments_on_restricted: false,
+            deposit_clearing_rows: 0,
+            deposit_clearing_days: 0,
+            dispute_expiry_rows: 0,
+            dispute_expiry_days: 0,
+            dispute_expiry_action: ExpiryAction::Resolve,
+            dispute_window_days: 0,
+            reorder_window_rows: 0,
+            reorder_buffer_size: 1000,
+            clients: Vec::new(),
+            fees: FeeSchedule::default(),
+        }
+    }
+}
+
+impl EnginePolicy {
+    /// Parse a policy from a TOML document. Missing fields keep their default value.
+    pub fn from_toml(input: &str) -> Result<Self, Error> {
+        let policy: Self = toml::from_str(input)?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Check the values that can't be expressed by the field types alone.
+    fn validate(&self) -> Result<(), Error> {
+        if self.reorder_window_rows > 0 && self.reorder_buffer_size == 0 {
+            return Err(Error::InvalidPolicy(
+                "reorder buffer size must be positive when reorder_window_rows is set".to_string(),
+            ));
+        }
+        for config in &self.clients {
+            if !(Decimal::ZERO..=Decimal::ONE_HUNDRED).contains(&config.reserve_percentage) {
+                return Err(Error::InvalidPolicy(format!(
+                    "reserve percentage of client {} must be between 0 and 100, got {}",
+                    config.client, config.reserve_percentage
+                )));
+            }
+            if config.credit_limit.is_sign_negative() {
+                return Err(Error::InvalidPolicy(format!(
+                    "credit limit of client {} must not be negative, got {}",
+                    config.client, config.credit_limit
+                )));
+            }
+        }
+        self.fees.validate().map_err(Error::InvalidPolicy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_toml_is_default_policy() {
+        assert_eq!(
+            EnginePolicy::from_toml("").unwrap(),
+            EnginePolicy::default()
+        );
+    }
+
+    #[test]
+    fn test_sample_policy_is_default_policy() {
+        let policy = EnginePolicy::from_toml(include_str!("../../samples/policy.toml")).unwrap();
+        assert_eq!(policy, EnginePolicy::default());
+    }
+
+    #[test]
+    fn test_toml_overrides_rules() {
+        let policy = EnginePolicy::from_toml(
+            "lock_on_chargeback = false\nallow_negative_available_on_dispute = false",
+        )
+        .unwrap();
+
+        assert!(!policy.lock_on_chargeback);
+        assert!(!policy.allow_negative_available_on_dispute);
+        assert!(policy.reject_deposits_on_locked);
+    }
+
+    #[test]
+    fn test_toml_sets_clearing_period() {
+        let policy = EnginePolicy::from_toml("deposit_clearing_rows = 3").unwrap();
+        assert_eq!(policy.deposit_clearing_rows, 3);
+
+        let policy = EnginePolicy::from_toml("deposit_clearing_days = 2").unwrap();
+        assert_eq!(policy.deposit_clearing_days, 2);
+    }
+
+    #[test]
+    fn test_toml_sets_dispute_expiry() {
+        let policy = EnginePolicy::from_toml(
+            "dispute_expiry_rows = 50\ndispute_expiry_action = \"chargeback\"",
+        )
+        .unwrap();
+        assert_eq!(policy.dispute_expiry_rows, 50);
+        assert_eq!(policy.dispute_expiry_action, ExpiryAction::Chargeback);
+
+        assert!(EnginePolicy::from_toml("dispute_expiry_action = \"refund\"").is_err());
+    }
+
+    #[test]
+    fn test_toml_sets_time_windows() {
+        let policy =
+            EnginePolicy::from_toml("dispute_window_days = 120\ndispute_expiry_days = 45").unwrap();
+        assert_eq!(policy.dispute_window_days, 120);
+        assert_eq!(policy.dispute_expiry_days, 45);
+    }
+
+    #[test]
+    fn test_toml_configures_reorder_buffer() {
+        let policy =
+            EnginePolicy::from_toml("reorder_window_rows = 10\nreorder_buffer_size = 50").unwrap();
+        assert_eq!(policy.reorder_window_rows, 10);
+        assert_eq!(policy.reorder_buffer_size, 50);
+
+        assert!(
+            EnginePolicy::from_toml("reorder_window_rows = 10\nreorder_buffer_size = 0").is_err()
+        );
+    }
+
+    #[test]
+    fn test_toml_configures_client_reserves() {
+        let policy = EnginePolicy::from_toml(
+            "[[clients]]\nclient = 7\nreserve_percentage = 12.5\nreserve_rows = 100\nreserve_days = 90",
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.clients,
+            vec![ClientConfig {
+                client: 7,
+                reserve_percentage: Decimal::new(125, 1),
+                reserve_rows: 100,
+                reserve_days: 90,
+                credit_limit: Decimal::ZERO,
+            }]
+        );
+        assert_eq!(
+            policy.clients[0].reserve_of(Decimal::new(3, 0), Currency::default()),
+            Decimal::new(375, 3)
+        );
+        assert_eq!(
+            policy.clients[0].reserve_of(Decimal::new(3, 0), "JPY".parse().unwrap()),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_toml_rejects_invalid_reserve_percentage() {
+        assert!(
+            EnginePolicy::from_toml("[[clients]]\nclient = 7\nreserve_percentage = 120").is_err()
+        );
+    }
+
+    #[test]
+    fn test_toml_configures_credit_limit() {
+        let policy =
+            EnginePolicy::from_toml("[[clients]]\nclient = 7\ncredit_limit = 250").unwrap();
+        assert_eq!(policy.clients[0].credit_limit, Decimal::new(250, 0));
+
+        assert!(EnginePolicy::from_toml("[[clients]]\nclient = 7\ncredit_limit = -1").is_err());
+    }
+
+    #[test]
+    fn test_toml_configures_fees() {
+        let policy = EnginePolicy::from_toml(
+            "[fees]\nhouse_account = 9\n[fees.deposit]\npercentage = 1\n\
+             [[fees.deposit.tiers]]\nfrom = 1000\npercentage = 0.5",
+        )
+        .unwrap();
+
+        let usd = Currency::default();
+        assert_eq!(policy.fees.house_account, Some(9));
+        assert_eq!(
+            policy.fees.deposit_fee(1, usd, Decimal::new(100, 0)),
+            Decimal::ONE
+        );
+        assert_eq!(
+            policy.fees.deposit_fee(1, usd, Decimal::new(2000, 0)),
+            Decimal::TEN
+        );
+        assert_eq!(
+            policy.fees.withdrawal_fee(1, usd, Decimal::new(100, 0)),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_toml_rejects_fees_without_house_account() {
+        assert!(EnginePolicy::from_toml("[fees.withdrawal]\nflat = 1").is_err());
+    }
+
+    #[test]
+    fn test_toml_rejects_unknown_rules() {
+        assert!(EnginePolicy::from_toml("lock_on_chargebacks = false").is_err());
+    }
+}
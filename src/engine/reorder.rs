@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use super::transaction::{Transaction, TransactionId, TransactionRecord};
+
+/// A row referencing a transaction that had not arrived when the row was read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmatchedRow {
+    row: u64,
+    record: TransactionRecord,
+    transaction: Transaction,
+}
+
+impl UnmatchedRow {
+    /// Returns the row number of the parked row in its input
+    pub fn row(&self) -> u64 {
+        self.row
+    }
+
+    /// Returns the row as it was read from the input
+    pub fn record(&self) -> &TransactionRecord {
+        &self.record
+    }
+
+    /// Returns the ID of the transaction the row references
+    pub fn referenced_tx_id(&self) -> TransactionId {
+        // Only referencing transactions are parked
+        self.transaction.referenced_tx_id().unwrap_or_default()
+    }
+
+    pub(super) fn into_parts(self) -> (u64, TransactionRecord, Transaction) {
+        (self.row, self.record, self.transaction)
+    }
+}
+
+impl std::fmt::Display for UnmatchedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[row {}] {}", self.row, self.transaction)
+    }
+}
+
+/// Bounded buffer of rows whose referenced transaction has not arrived yet.
+///
+/// Partners sometimes deliver a dispute a few rows before the deposit it references. Such rows
+/// wait here for up to `window` rows and are retried once their target is applied. Rows that
+/// wait too long, or are pushed out because the buffer is full, are never matched.
+#[derive(Debug, Default)]
+pub(super) struct ReorderBuffer {
+    /// Parked rows by row number, so the oldest row comes first
+    parked: BTreeMap<u64, UnmatchedRow>,
+    /// Number of subsequent rows a row waits for its target, `0` disables the buffer
+    window: u64,
+    /// Maximum number of parked rows
+    capacity: usize,
+}
+
+impl ReorderBuffer {
+    pub(super) fn new(window: u64, capacity: usize) -> Self {
+        Self {
+            parked: BTreeMap::new(),
+            window,
+            capacity,
+        }
+    }
+
+    /// Returns whether rows referencing unknown transactions are parked
+    pub(super) fn is_enabled(&self) -> bool {
+        self.window > 0 && self.capacity > 0
+    }
+
+    /// Park a referencing row, returns the oldest parked row if it had to make room.
+    pub(super) fn park(
+        &mut self,
+        row: u64,
+        record: TransactionRecord,
+        transaction: Transaction,
+    ) -> Option<UnmatchedRow> {
+        debug_assert!(
+            transaction.referenced_tx_id().is_some(),
+            "park called with a transaction that references nothing"
+        );
+        self.parked.insert(
+            row,
+            UnmatchedRow {
+                row,
+                record,
+                transaction,
+            },
+        );
+        if self.parked.len() > self.capacity {
+            return self.parked.pop_first().map(|(_, evicted)| evicted);
+        }
+        None
+    }
+
+    /// Take every parked row referencing `tx_id`, in row order
+    pub(super) fn take_matching(&mut self, tx_id: TransactionId) -> Vec<UnmatchedRow> {
+        let rows: Vec<u64> = self
+            .parked
+            .iter()
+            .filter(|(_, parked)| parked.transaction.referenced_tx_id() == Some(tx_id))
+            .map(|(row, _)| *row)
+            .collect();
+        rows.iter()
+            .filter_map(|row| self.parked.remove(row))
+            .collect()
+    }
+
+    /// Take every parked row that waited `window` rows by `row`
+    pub(super) fn take_expired(&mut self, row: u64) -> Vec<UnmatchedRow> {
+        let mut expired = Vec::new();
+        while let Some(oldest) = self.parked.first_entry() {
+            if *oldest.key() + self.window > row {
+                break;
+            }
+            expired.push(oldest.remove());
+        }
+        expired
+    }
+
+    /// Take every parked row, e.g. at the end of the input
+    pub(super) fn drain(&mut self) -> Vec<UnmatchedRow> {
+        std::mem::take(&mut self.parked).into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::transaction::TransactionType;
+
+    fn dispute(referenced_tx_id: TransactionId) -> (TransactionRecord, Transaction) {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Dispute,
+            client: 1,
+            tx: referenced_tx_id,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        (record.clone(), Transaction::try_from(record).unwrap())
+    }
+
+    fn park(buffer: &mut ReorderBuffer, row: u64, referenced_tx_id: TransactionId) -> bool {
+        let (record, transaction) = dispute(referenced_tx_id);
+        buffer.park(row, record, transaction).is_none()
+    }
+
+    #[test]
+    fn test_takes_matching_rows_in_order() {
+        let mut buffer = ReorderBuffer::new(10, 10);
+        park(&mut buffer, 1, 7);
+        park(&mut buffer, 2, 8);
+        park(&mut buffer, 3, 7);
+
+        let matching = buffer.take_matching(7);
+        assert_eq!(
+            matching.iter().map(UnmatchedRow::row).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(buffer.drain().len(), 1);
+    }
+
+    #[test]
+    fn test_rows_expire_after_window() {
+        let mut buffer = ReorderBuffer::new(2, 10);
+        park(&mut buffer, 1, 7);
+        park(&mut buffer, 2, 8);
+
+        assert!(buffer.take_expired(2).is_empty());
+        let expired = buffer.take_expired(3);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].referenced_tx_id(), 7);
+    }
+
+    #[test]
+    fn test_full_buffer_evicts_oldest_row() {
+        let mut buffer = ReorderBuffer::new(10, 2);
+        assert!(park(&mut buffer, 1, 7));
+        assert!(park(&mut buffer, 2, 8));
+
+        let (record, transaction) = dispute(9);
+        let evicted = buffer.park(3, record, transaction).unwrap();
+        assert_eq!(evicted.row(), 1);
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use super::account::ClientId;
+use super::currency::Currency;
+use super::error::ProcessingError;
+use super::payment_engine::Outcome;
+use super::transaction::{Transaction, TransactionRecord, TransactionType};
+use super::Decimal;
+
+/// Summary of one `PaymentEngine::process_transactions` call.
+///
+/// Counts the rows by transaction type and outcome, sums the amounts of applied rows and keeps
+/// every rejected row with the reason it was rejected. Serializes to JSON for reports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProcessingReport {
+    /// Number of rows read
+    rows: u64,
+    /// Number of rows applied, including rows applied after waiting in the reorder buffer
+    applied: u64,
+    /// Number of rows ignored as exact replays of applied transactions
+    replayed: u64,
+    /// Number of rows rejected
+    rejected: u64,
+    /// Rows read per transaction type
+    rows_by_type: BTreeMap<TransactionType, u64>,
+    /// Rejected rows per `ProcessingError::kind`
+    errors: BTreeMap<&'static str, u64>,
+    /// Sum of the amounts of applied rows per transaction type and currency
+    amounts: BTreeMap<TransactionType, BTreeMap<Currency, Decimal>>,
+    /// Clients whose accounts were changed by applied rows
+    clients: BTreeSet<ClientId>,
+    /// Every rejected row, in the order they were rejected
+    rejected_rows: Vec<RejectedRow>,
+}
+
+/// A row that was not applied, with its input record and the reason.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RejectedRow {
+    row: u64,
+    record: TransactionRecord,
+    /// `ProcessingError::kind` of the error that rejected the row
+    kind: &'static str,
+    reason: String,
+}
+
+impl RejectedRow {
+    /// Returns the row number in the input
+    pub fn row(&self) -> u64 {
+        self.row
+    }
+
+    /// Returns the row as it was read from the input
+    pub fn record(&self) -> &TransactionRecord {
+        &self.record
+    }
+
+    /// Returns the kind of the error that rejected the row, see `ProcessingError::kind`
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Returns the error message that rejected the row
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl ProcessingReport {
+    /// Returns the number of rows read
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Returns the number of rows applied
+    pub fn applied(&self) -> u64 {
+        self.applied
+    }
+
+    /// Returns the number of rows ignored as exact replays
+    pub fn replayed(&self) -> u64 {
+        self.replayed
+    }
+
+    /// Returns the number of rows rejected
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Returns the number of rows read of a transaction type
+    pub fn rows_of_type(&self, tx_type: TransactionType) -> u64 {
+        self.rows_by_type.get(&tx_type).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of rows rejected with an error kind, see `ProcessingError::kind`
+    pub fn errors_of_kind(&self, kind: &str) -> u64 {
+        self.errors.get(kind).copied().unwrap_or_default()
+    }
+
+    /// Returns the sum of the amounts of applied rows of a transaction type in a currency.
+    ///
+    /// Only deposits, withdrawals, transfers, conversions, authorizations and adjustments are
+    /// summed, disputes and the other rows referencing a transaction may not carry an amount.
+    pub fn amount(&self, tx_type: TransactionType, currency: Currency) -> Decimal {
+        self.amounts
+            .get(&tx_type)
+            .and_then(|amounts| amounts.get(&currency))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the clients whose accounts were changed by applied rows
+    pub fn clients(&self) -> &BTreeSet<ClientId> {
+        &self.clients
+    }
+
+    /// Returns every rejected row, in the order they were rejected
+    pub fn rejected_rows(&self) -> &[RejectedRow] {
+        &self.rejected_rows
+    }
+
+    /// Count a row read from the input
+    pub(super) fn read(&mut self, tx_type: TransactionType) {
+        self.rows += 1;
+        *self.rows_by_type.entry(tx_type).or_default() += 1;
+    }
+
+    /// Count a row that was applied or ignored as a replay
+    pub(super) fn apply(
+        &mut self,
+        tx_type: TransactionType,
+        transaction: &Transaction,
+        outcome: Outcome,
+    ) {
+        if outcome == Outcome::Replayed {
+            self.replayed += 1;
+            return;
+        }
+        self.applied += 1;
+        self.clients.insert(transaction.client_id());
+        if let Transaction::Transfer(transfer) = transaction {
+            self.clients.insert(transfer.recipient_id());
+        }
+        if let Some((amount, currency)) = money(transaction) {
+            *self
+                .amounts
+                .entry(tx_type)
+                .or_default()
+                .entry(currency)
+                .or_default() += amount;
+        }
+    }
+
+    /// Count a row that failed with `error`
+    pub(super) fn reject(&mut self, row: u64, record: TransactionRecord, error: &ProcessingError) {
+        self.reject_with(row, record, error.kind(), error.to_string());
+    }
+
+    /// Count a row that was rejected for `reason`
+    pub(super) fn reject_with(
+        &mut self,
+        row: u64,
+        record: TransactionRecord,
+        kind: &'static str,
+        reason: String,
+    ) {
+        self.rejected += 1;
+        *self.errors.entry(kind).or_default() += 1;
+        self.rejected_rows.push(RejectedRow {
+            row,
+            record,
+            kind,
+            reason,
+        });
+    }
+}
+
+/// Returns the amount a transaction moves and its currency, for the transactions that always
+/// carry an amount
+fn money(transaction: &Transaction) -> Option<(Decimal, Currency)> {
+    match transaction {
+        Transaction::Deposit(d) => Some((d.amount(), d.currency())),
+        Transaction::Withdrawal(w) => Some((w.amount(), w.currency())),
+        Transaction::Transfer(t) => Some((t.amount(), t.currency())),
+        Transaction::Convert(c) => Some((c.amount(), c.currency())),
+        Transaction::Authorize(a) => Some((a.amount(), a.currency())),
+        Transaction::Adjustment(a) => Some((a.amount(), a.currency())),
+        Transaction::Capture(_)
+        | Transaction::Void(_)
+        | Transaction::Dispute(_)
+        | Transaction::Resolve(_)
+        | Transaction::Chargeback(_)
+        | Transaction::Refund(_)
+        | Transaction::Freeze(_)
+        | Transaction::Unfreeze(_)
+        | Transaction::Unlock(_)
+        | Transaction::Restrict(_)
+        | Transaction::CreditLimit(_)
+        | Transaction::Close(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn record(tx_type: TransactionType, tx: u32, amount: Decimal) -> TransactionRecord {
+        TransactionRecord {
+            tx_type,
+            client: 1,
+            tx,
+            amount: Some(amount),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_sums_applied_amounts_per_type_and_currency() {
+        let mut report = ProcessingReport::default();
+        for (tx, amount) in [(1, dec!(10)), (2, dec!(2.5))] {
+            let record = record(TransactionType::Deposit, tx, amount);
+            let transaction = Transaction::try_from(record).unwrap();
+            report.read(TransactionType::Deposit);
+            report.apply(TransactionType::Deposit, &transaction, Outcome::Applied);
+        }
+        let replay = Transaction::try_from(record(TransactionType::Deposit, 2, dec!(2.5))).unwrap();
+        report.read(TransactionType::Deposit);
+        report.apply(TransactionType::Deposit, &replay, Outcome::Replayed);
+
+        assert_eq!(report.rows(), 3);
+        assert_eq!(report.applied(), 2);
+        assert_eq!(report.replayed(), 1);
+        assert_eq!(report.rows_of_type(TransactionType::Deposit), 3);
+        assert_eq!(
+            report.amount(TransactionType::Deposit, Currency::default()),
+            dec!(12.5)
+        );
+        assert_eq!(
+            report.clients().iter().copied().collect::<Vec<_>>(),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_counts_rejected_rows_per_kind() {
+        let mut report = ProcessingReport::default();
+        let error = ProcessingError::AccountNotFound { client: 1 };
+        report.read(TransactionType::Withdrawal);
+        report.reject(1, record(TransactionType::Withdrawal, 1, dec!(5)), &error);
+
+        assert_eq!(report.rejected(), 1);
+        assert_eq!(report.errors_of_kind("account_not_found"), 1);
+        assert_eq!(report.rejected_rows()[0].row(), 1);
+        assert_eq!(report.rejected_rows()[0].reason(), "Account 1 not found");
+    }
+}
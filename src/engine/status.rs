@@ -0,0 +1,141 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// Why an account is frozen. Each reason is lifted independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FreezeReason {
+    /// Locked by a chargeback, lifted by an `unlock`
+    Chargeback,
+    /// Frozen by compliance, lifted by an `unfreeze`
+    Compliance,
+}
+
+impl std::fmt::Display for FreezeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FreezeReason::Chargeback => write!(f, "chargeback"),
+            FreezeReason::Compliance => write!(f, "compliance"),
+        }
+    }
+}
+
+impl FromStr for FreezeReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chargeback" => Ok(FreezeReason::Chargeback),
+            "compliance" => Ok(FreezeReason::Compliance),
+            _ => Err(format!("unknown freeze reason: {s}")),
+        }
+    }
+}
+
+/// Operator restriction of the transactions an active account accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Restriction {
+    /// Deposits and withdrawals are accepted
+    #[default]
+    None,
+    /// Only withdrawals are accepted
+    WithdrawOnly,
+    /// Only deposits are accepted
+    DepositOnly,
+}
+
+/// Effective status of an account, as exported in the `status` column.
+///
+/// Closing takes precedence over freezes, and freezes take precedence over restrictions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountStatus {
+    Active,
+    WithdrawOnly,
+    DepositOnly,
+    /// Frozen for one or more reasons
+    Frozen(BTreeSet<FreezeReason>),
+    Closed,
+}
+
+impl AccountStatus {
+    /// Returns whether the account is locked, i.e. frozen or closed
+    pub fn is_locked(&self) -> bool {
+        matches!(self, AccountStatus::Frozen(_) | AccountStatus::Closed)
+    }
+}
+
+impl std::fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountStatus::Active => write!(f, "active"),
+            AccountStatus::WithdrawOnly => write!(f, "withdraw_only"),
+            AccountStatus::DepositOnly => write!(f, "deposit_only"),
+            AccountStatus::Frozen(reasons) => {
+                let reasons: Vec<_> = reasons.iter().map(ToString::to_string).collect();
+                write!(f, "frozen:{}", reasons.join("+"))
+            }
+            AccountStatus::Closed => write!(f, "closed"),
+        }
+    }
+}
+
+impl FromStr for AccountStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(AccountStatus::Active),
+            "withdraw_only" => Ok(AccountStatus::WithdrawOnly),
+            "deposit_only" => Ok(AccountStatus::DepositOnly),
+            "closed" => Ok(AccountStatus::Closed),
+            _ => {
+                let reasons = s
+                    .strip_prefix("frozen:")
+                    .ok_or_else(|| format!("unknown account status: {s}"))?;
+                let reasons = reasons
+                    .split('+')
+                    .map(FreezeReason::from_str)
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+                Ok(AccountStatus::Frozen(reasons))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trip() {
+        let statuses = [
+            AccountStatus::Active,
+            AccountStatus::WithdrawOnly,
+            AccountStatus::DepositOnly,
+            AccountStatus::Frozen(BTreeSet::from([FreezeReason::Compliance])),
+            AccountStatus::Frozen(BTreeSet::from([
+                FreezeReason::Chargeback,
+                FreezeReason::Compliance,
+            ])),
+            AccountStatus::Closed,
+        ];
+
+        for status in statuses {
+            assert_eq!(status.to_string().parse::<AccountStatus>(), Ok(status));
+        }
+    }
+
+    #[test]
+    fn test_frozen_status_format() {
+        let status = AccountStatus::Frozen(BTreeSet::from([
+            FreezeReason::Compliance,
+            FreezeReason::Chargeback,
+        ]));
+        assert_eq!(status.to_string(), "frozen:chargeback+compliance");
+    }
+
+    #[test]
+    fn test_rejects_unknown_status() {
+        assert!("suspended".parse::<AccountStatus>().is_err());
+        assert!("frozen:fraud".parse::<AccountStatus>().is_err());
+    }
+}
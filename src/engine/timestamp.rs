@@ -0,0 +1,106 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Point in time of a row, from the optional `timestamp` column.
+///
+/// The column accepts RFC 3339 timestamps such as `2024-03-01T12:00:00Z` or
+/// `2024-03-01T13:00:00+01:00`, and milliseconds since the Unix epoch such as
+/// `1709294400000`. Timestamps are kept with millisecond precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    /// Milliseconds in a day, the unit of the policy's time windows
+    pub const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+    /// Create a timestamp from milliseconds since the Unix epoch
+    pub fn from_millis(millis: i64) -> Self {
+        Self(millis)
+    }
+
+    /// Returns the milliseconds since the Unix epoch
+    pub fn as_millis(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns the timestamp `days` days later
+    #[must_use]
+    pub fn add_days(self, days: u64) -> Self {
+        let days = i64::try_from(days).unwrap_or(i64::MAX);
+        Self(
+            self.0
+                .saturating_add(days.saturating_mul(Self::MILLIS_PER_DAY)),
+        )
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(millis) = s.parse::<i64>() {
+            return Ok(Self(millis));
+        }
+        s.parse::<jiff::Timestamp>()
+            .map(|timestamp| Self(timestamp.as_millisecond()))
+            .map_err(|e| format!("invalid timestamp {s}: {e}"))
+    }
+}
+
+impl TryFrom<String> for Timestamp {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Timestamp> for String {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_string()
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    /// Formats the timestamp as RFC 3339 in UTC, or as epoch milliseconds if it is out of the
+    /// supported range
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match jiff::Timestamp::from_millisecond(self.0) {
+            Ok(timestamp) => write!(f, "{timestamp}"),
+            Err(_) => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_rfc3339_and_epoch_millis() {
+        let rfc3339: Timestamp = "2024-03-01T12:00:00Z".parse().unwrap();
+        let offset: Timestamp = "2024-03-01T13:00:00+01:00".parse().unwrap();
+        let millis: Timestamp = "1709294400000".parse().unwrap();
+
+        assert_eq!(rfc3339.as_millis(), 1_709_294_400_000);
+        assert_eq!(rfc3339, offset);
+        assert_eq!(rfc3339, millis);
+        assert_eq!(rfc3339.to_string(), "2024-03-01T12:00:00Z");
+    }
+
+    #[test]
+    fn test_add_days() {
+        let timestamp: Timestamp = "2024-02-28T00:00:00Z".parse().unwrap();
+        assert_eq!(timestamp.add_days(2).to_string(), "2024-03-01T00:00:00Z");
+        assert_eq!(timestamp.add_days(u64::MAX).as_millis(), i64::MAX);
+    }
+
+    #[test]
+    fn test_rejects_invalid_timestamps() {
+        for timestamp in ["", "yesterday", "2024-13-01T00:00:00Z", "2024-03-01"] {
+            assert!(timestamp.parse::<Timestamp>().is_err(), "{timestamp}");
+        }
+    }
+}
@@ -1,59 +1,139 @@
+mod adjustment;
+mod authorize;
+mod capture;
 mod chargeback;
+mod close;
+mod convert;
+mod credit_limit;
 mod deposit;
 mod dispute;
+mod freeze;
+mod refund;
 mod resolve;
+mod restrict;
+mod transfer;
+mod unfreeze;
+mod unlock;
+mod void;
 mod withdrawal;
 
+pub use adjustment::Adjustment;
+pub use authorize::Authorize;
+pub use capture::Capture;
 pub use chargeback::Chargeback;
+pub use close::Close;
+pub use convert::Convert;
+pub use credit_limit::CreditLimit;
 pub use deposit::Deposit;
 pub use dispute::Dispute;
+pub use freeze::Freeze;
+pub use refund::Refund;
 pub use resolve::Resolve;
+pub use restrict::Restrict;
+pub use transfer::Transfer;
+pub use unfreeze::Unfreeze;
+pub use unlock::Unlock;
+pub use void::Void;
 pub use withdrawal::Withdrawal;
 
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use super::account::ClientId;
+use super::currency::Currency;
+use super::timestamp::Timestamp;
 use super::Decimal;
 use crate::engine::error::TransactionError;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 pub type TransactionId = u32;
 
 /// Raw transaction record as parsed from CSV input.
 /// This is the unvalidated form that needs conversion to a specific Transaction type.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TransactionRecord {
     #[serde(rename = "type")]
     pub tx_type: TransactionType,
     pub client: u16,
-    /// Transaction ID (for Deposit/Withdrawal) or Reference ID (for Dispute/Resolve/Chargeback)
+    /// Transaction ID (for Deposit/Withdrawal/Transfer/Convert/Authorize and administrative
+    /// actions) or Reference ID (for Dispute/Resolve/Chargeback/Refund/Capture/Void)
     pub tx: u32,
-    /// Amount: required for Deposit/Withdrawal, must be None for Dispute/Resolve/Chargeback
+    /// Amount: required for Deposit/Withdrawal/Transfer/Convert/Authorize, the new limit for
+    /// `credit_limit`, optional partial amount for Dispute/Resolve/Chargeback/Refund
     pub amount: Option<Decimal>,
+    /// Receiving client of a Transfer, the `to` column is optional and empty for every other type
+    #[serde(default)]
+    pub to: Option<u16>,
+    /// Reason code of an Adjustment, the `reason` column is optional and empty for every other type
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Currency of the row, the `currency` column is optional and defaults to `Currency::default()`
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    /// Currency a Convert exchanges into, the `to_currency` column is optional and empty for
+    /// every other type
+    #[serde(default)]
+    pub to_currency: Option<Currency>,
+    /// When the transaction happened, the `timestamp` column is optional and holds RFC 3339
+    /// timestamps or milliseconds since the Unix epoch
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>,
 }
 
 impl std::fmt::Display for TransactionRecord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.amount {
-            Some(amount) => write!(
-                f,
-                "{} (client: {}, tx: {}, amount: {})",
-                self.tx_type, self.client, self.tx, amount
-            ),
-            None => write!(
-                f,
-                "{} (client: {}, tx: {})",
-                self.tx_type, self.client, self.tx
-            ),
+        write!(
+            f,
+            "{} (client: {}, tx: {}",
+            self.tx_type, self.client, self.tx
+        )?;
+        if let Some(amount) = self.amount {
+            write!(f, ", amount: {amount}")?;
+        }
+        if let Some(to) = self.to {
+            write!(f, ", to: {to}")?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ", reason: {reason}")?;
+        }
+        if let Some(currency) = self.currency {
+            write!(f, ", currency: {currency}")?;
         }
+        if let Some(to_currency) = self.to_currency {
+            write!(f, ", to_currency: {to_currency}")?;
+        }
+        if let Some(timestamp) = self.timestamp {
+            write!(f, ", timestamp: {timestamp}")?;
+        }
+        write!(f, ")")
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionType {
     Deposit,
     Withdrawal,
+    Transfer,
+    Convert,
+    Authorize,
+    Capture,
+    Void,
     Dispute,
     Resolve,
     Chargeback,
+    Refund,
+    Adjustment,
+    Freeze,
+    Unfreeze,
+    Unlock,
+    #[serde(rename = "withdraw_only")]
+    WithdrawOnly,
+    #[serde(rename = "deposit_only")]
+    DepositOnly,
+    Activate,
+    #[serde(rename = "credit_limit")]
+    CreditLimit,
+    Close,
 }
 
 impl std::fmt::Display for TransactionType {
@@ -61,21 +141,157 @@ impl std::fmt::Display for TransactionType {
         match self {
             TransactionType::Deposit => write!(f, "deposit"),
             TransactionType::Withdrawal => write!(f, "withdrawal"),
+            TransactionType::Transfer => write!(f, "transfer"),
+            TransactionType::Convert => write!(f, "convert"),
+            TransactionType::Authorize => write!(f, "authorize"),
+            TransactionType::Capture => write!(f, "capture"),
+            TransactionType::Void => write!(f, "void"),
             TransactionType::Dispute => write!(f, "dispute"),
             TransactionType::Resolve => write!(f, "resolve"),
             TransactionType::Chargeback => write!(f, "chargeback"),
+            TransactionType::Refund => write!(f, "refund"),
+            TransactionType::Adjustment => write!(f, "adjustment"),
+            TransactionType::Freeze => write!(f, "freeze"),
+            TransactionType::Unfreeze => write!(f, "unfreeze"),
+            TransactionType::Unlock => write!(f, "unlock"),
+            TransactionType::WithdrawOnly => write!(f, "withdraw_only"),
+            TransactionType::DepositOnly => write!(f, "deposit_only"),
+            TransactionType::Activate => write!(f, "activate"),
+            TransactionType::CreditLimit => write!(f, "credit_limit"),
+            TransactionType::Close => write!(f, "close"),
         }
     }
 }
 
 /// A validated transaction ready for processing by the payment engine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum Transaction {
     Deposit(Deposit),
     Withdrawal(Withdrawal),
+    Transfer(Transfer),
+    Convert(Convert),
+    Authorize(Authorize),
+    Capture(Capture),
+    Void(Void),
     Dispute(Dispute),
     Resolve(Resolve),
     Chargeback(Chargeback),
+    Refund(Refund),
+    Adjustment(Adjustment),
+    Freeze(Freeze),
+    Unfreeze(Unfreeze),
+    Unlock(Unlock),
+    Restrict(Restrict),
+    CreditLimit(CreditLimit),
+    Close(Close),
+}
+
+impl Transaction {
+    /// Returns the client the row belongs to, the sender of a transfer.
+    pub fn client_id(&self) -> ClientId {
+        match self {
+            Transaction::Deposit(d) => d.client_id(),
+            Transaction::Withdrawal(w) => w.client_id(),
+            Transaction::Transfer(t) => t.client_id(),
+            Transaction::Convert(c) => c.client_id(),
+            Transaction::Authorize(a) => a.client_id(),
+            Transaction::Capture(c) => c.client_id(),
+            Transaction::Void(v) => v.client_id(),
+            Transaction::Dispute(d) => d.client_id(),
+            Transaction::Resolve(r) => r.client_id(),
+            Transaction::Chargeback(c) => c.client_id(),
+            Transaction::Refund(r) => r.client_id(),
+            Transaction::Adjustment(a) => a.client_id(),
+            Transaction::Freeze(f) => f.client_id(),
+            Transaction::Unfreeze(u) => u.client_id(),
+            Transaction::Unlock(u) => u.client_id(),
+            Transaction::Restrict(r) => r.client_id(),
+            Transaction::CreditLimit(c) => c.client_id(),
+            Transaction::Close(c) => c.client_id(),
+        }
+    }
+
+    /// Returns the globally unique ID of this transaction, if it carries its own.
+    /// Dispute/Resolve/Chargeback/Refund/Capture/Void only reference another transaction,
+    /// so they return `None`.
+    pub fn transaction_id(&self) -> Option<TransactionId> {
+        match self {
+            Transaction::Deposit(d) => Some(d.transaction_id()),
+            Transaction::Withdrawal(w) => Some(w.transaction_id()),
+            Transaction::Transfer(t) => Some(t.transaction_id()),
+            Transaction::Convert(c) => Some(c.transaction_id()),
+            Transaction::Authorize(a) => Some(a.transaction_id()),
+            Transaction::Adjustment(a) => Some(a.transaction_id()),
+            Transaction::Freeze(f) => Some(f.transaction_id()),
+            Transaction::Unfreeze(u) => Some(u.transaction_id()),
+            Transaction::Unlock(u) => Some(u.transaction_id()),
+            Transaction::Restrict(r) => Some(r.transaction_id()),
+            Transaction::CreditLimit(c) => Some(c.transaction_id()),
+            Transaction::Close(c) => Some(c.transaction_id()),
+            Transaction::Dispute(_)
+            | Transaction::Resolve(_)
+            | Transaction::Chargeback(_)
+            | Transaction::Refund(_)
+            | Transaction::Capture(_)
+            | Transaction::Void(_) => None,
+        }
+    }
+
+    /// Returns the ID of the transaction this one references,
+    /// for Dispute/Resolve/Chargeback/Refund/Capture/Void.
+    pub fn referenced_tx_id(&self) -> Option<TransactionId> {
+        match self {
+            Transaction::Dispute(d) => Some(d.referenced_tx_id()),
+            Transaction::Resolve(r) => Some(r.referenced_tx_id()),
+            Transaction::Chargeback(c) => Some(c.referenced_tx_id()),
+            Transaction::Refund(r) => Some(r.referenced_tx_id()),
+            Transaction::Capture(c) => Some(c.referenced_tx_id()),
+            Transaction::Void(v) => Some(v.referenced_tx_id()),
+            Transaction::Deposit(_)
+            | Transaction::Withdrawal(_)
+            | Transaction::Transfer(_)
+            | Transaction::Convert(_)
+            | Transaction::Authorize(_)
+            | Transaction::Adjustment(_)
+            | Transaction::Freeze(_)
+            | Transaction::Unfreeze(_)
+            | Transaction::Unlock(_)
+            | Transaction::Restrict(_)
+            | Transaction::CreditLimit(_)
+            | Transaction::Close(_) => None,
+        }
+    }
+
+    /// Returns when the transaction happened, if the input carries timestamps.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Transaction::Deposit(d) => d.timestamp(),
+            Transaction::Withdrawal(w) => w.timestamp(),
+            Transaction::Transfer(t) => t.timestamp(),
+            Transaction::Convert(c) => c.timestamp(),
+            Transaction::Authorize(a) => a.timestamp(),
+            Transaction::Capture(c) => c.timestamp(),
+            Transaction::Void(v) => v.timestamp(),
+            Transaction::Dispute(d) => d.timestamp(),
+            Transaction::Resolve(r) => r.timestamp(),
+            Transaction::Chargeback(c) => c.timestamp(),
+            Transaction::Refund(r) => r.timestamp(),
+            Transaction::Adjustment(a) => a.timestamp(),
+            Transaction::Freeze(f) => f.timestamp(),
+            Transaction::Unfreeze(u) => u.timestamp(),
+            Transaction::Unlock(u) => u.timestamp(),
+            Transaction::Restrict(r) => r.timestamp(),
+            Transaction::CreditLimit(c) => c.timestamp(),
+            Transaction::Close(c) => c.timestamp(),
+        }
+    }
+
+    /// Returns a compact hash of every field, equal transactions have equal fingerprints.
+    pub(super) fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
 impl TryFrom<TransactionRecord> for Transaction {
@@ -87,11 +303,30 @@ impl TryFrom<TransactionRecord> for Transaction {
             TransactionType::Withdrawal => {
                 Ok(Transaction::Withdrawal(Withdrawal::try_from(record)?))
             }
+            TransactionType::Transfer => Ok(Transaction::Transfer(Transfer::try_from(record)?)),
+            TransactionType::Convert => Ok(Transaction::Convert(Convert::try_from(record)?)),
+            TransactionType::Authorize => Ok(Transaction::Authorize(Authorize::try_from(record)?)),
+            TransactionType::Capture => Ok(Transaction::Capture(Capture::try_from(record)?)),
+            TransactionType::Void => Ok(Transaction::Void(Void::try_from(record)?)),
             TransactionType::Dispute => Ok(Transaction::Dispute(Dispute::try_from(record)?)),
             TransactionType::Resolve => Ok(Transaction::Resolve(Resolve::try_from(record)?)),
             TransactionType::Chargeback => {
                 Ok(Transaction::Chargeback(Chargeback::try_from(record)?))
             }
+            TransactionType::Refund => Ok(Transaction::Refund(Refund::try_from(record)?)),
+            TransactionType::Adjustment => {
+                Ok(Transaction::Adjustment(Adjustment::try_from(record)?))
+            }
+            TransactionType::Freeze => Ok(Transaction::Freeze(Freeze::try_from(record)?)),
+            TransactionType::Unfreeze => Ok(Transaction::Unfreeze(Unfreeze::try_from(record)?)),
+            TransactionType::Unlock => Ok(Transaction::Unlock(Unlock::try_from(record)?)),
+            TransactionType::WithdrawOnly
+            | TransactionType::DepositOnly
+            | TransactionType::Activate => Ok(Transaction::Restrict(Restrict::try_from(record)?)),
+            TransactionType::CreditLimit => {
+                Ok(Transaction::CreditLimit(CreditLimit::try_from(record)?))
+            }
+            TransactionType::Close => Ok(Transaction::Close(Close::try_from(record)?)),
         }
     }
 }
@@ -99,47 +334,125 @@ impl TryFrom<TransactionRecord> for Transaction {
 impl std::fmt::Display for Transaction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Transaction::Deposit(d) => {
-                write!(
-                    f,
-                    "[deposit] client={} tx={} amount={}",
-                    d.client_id(),
-                    d.transaction_id(),
-                    d.amount()
-                )
+            Transaction::Deposit(d) => write!(
+                f,
+                "[deposit] client={} tx={} amount={}",
+                d.client_id(),
+                d.transaction_id(),
+                d.amount()
+            ),
+            Transaction::Withdrawal(w) => write!(
+                f,
+                "[withdrawal] client={} amount={}",
+                w.client_id(),
+                w.amount()
+            ),
+            Transaction::Transfer(t) => write!(
+                f,
+                "[transfer] client={} to={} tx={} amount={}",
+                t.client_id(),
+                t.recipient_id(),
+                t.transaction_id(),
+                t.amount()
+            ),
+            Transaction::Convert(c) => write!(
+                f,
+                "[convert] client={} tx={} amount={} {} to={}",
+                c.client_id(),
+                c.transaction_id(),
+                c.amount(),
+                c.currency(),
+                c.target_currency()
+            ),
+            Transaction::Authorize(a) => write!(
+                f,
+                "[authorize] client={} tx={} amount={}",
+                a.client_id(),
+                a.transaction_id(),
+                a.amount()
+            ),
+            Transaction::Capture(c) => {
+                write_reference(f, "capture", c.client_id(), c.referenced_tx_id(), None)
+            }
+            Transaction::Void(v) => {
+                write_reference(f, "void", v.client_id(), v.referenced_tx_id(), None)
             }
-            Transaction::Withdrawal(w) => {
-                write!(
-                    f,
-                    "[withdrawal] client={} amount={}",
-                    w.client_id(),
-                    w.amount()
-                )
+            Transaction::Dispute(d) => write_reference(
+                f,
+                "dispute",
+                d.client_id(),
+                d.referenced_tx_id(),
+                d.amount(),
+            ),
+            Transaction::Resolve(r) => write_reference(
+                f,
+                "resolve",
+                r.client_id(),
+                r.referenced_tx_id(),
+                r.amount(),
+            ),
+            Transaction::Chargeback(c) => write_reference(
+                f,
+                "chargeback",
+                c.client_id(),
+                c.referenced_tx_id(),
+                c.amount(),
+            ),
+            Transaction::Refund(r) => {
+                write_reference(f, "refund", r.client_id(), r.referenced_tx_id(), r.amount())
             }
-            Transaction::Dispute(d) => {
-                write!(
-                    f,
-                    "[dispute] client={} ref_tx={}",
-                    d.client_id(),
-                    d.referenced_tx_id()
-                )
+            Transaction::Adjustment(a) => write!(
+                f,
+                "[adjustment] client={} tx={} amount={} reason={}",
+                a.client_id(),
+                a.transaction_id(),
+                a.amount(),
+                a.reason()
+            ),
+            Transaction::Freeze(fr) => {
+                write_admin(f, "freeze", fr.client_id(), fr.transaction_id())
             }
-            Transaction::Resolve(r) => {
-                write!(
-                    f,
-                    "[resolve] client={} ref_tx={}",
-                    r.client_id(),
-                    r.referenced_tx_id()
-                )
+            Transaction::Unfreeze(u) => {
+                write_admin(f, "unfreeze", u.client_id(), u.transaction_id())
             }
-            Transaction::Chargeback(c) => {
-                write!(
-                    f,
-                    "[chargeback] client={} ref_tx={}",
-                    c.client_id(),
-                    c.referenced_tx_id()
-                )
+            Transaction::Unlock(u) => write_admin(f, "unlock", u.client_id(), u.transaction_id()),
+            Transaction::Restrict(r) => write!(
+                f,
+                "[restrict] client={} tx={} restriction={:?}",
+                r.client_id(),
+                r.transaction_id(),
+                r.restriction()
+            ),
+            Transaction::CreditLimit(c) => {
+                write_admin(f, "credit_limit", c.client_id(), c.transaction_id())?;
+                write!(f, " limit={}", c.limit())
             }
+            Transaction::Close(c) => write_admin(f, "close", c.client_id(), c.transaction_id()),
         }
     }
 }
+
+/// Write a transaction that references another transaction and may carry a partial amount.
+fn write_reference(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    client_id: u16,
+    referenced_tx_id: TransactionId,
+    amount: Option<Decimal>,
+) -> std::fmt::Result {
+    write!(f, "[{name}] client={client_id} ref_tx={referenced_tx_id}")?;
+    if let Some(amount) = amount {
+        write!(f, " amount={amount}")?;
+    }
+    Ok(())
+}
+
+/// Write an administrative action, which carries no amount.
+fn write_admin(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    client_id: u16,
+    transaction_id: TransactionId,
+) -> std::fmt::Result {
+    write!(f, "[{name}] client={client_id} tx={transaction_id}")
+}
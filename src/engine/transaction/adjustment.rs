@@ -0,0 +1,141 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+    Decimal,
+};
+
+/// A validated operator adjustment.
+///
+/// Adjustments correct a client's balance manually: a positive amount credits and a negative
+/// amount debits available and total funds. Every adjustment carries a mandatory reason code
+/// and is kept apart from customer deposits and withdrawals, so it can't be disputed.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Adjustment {
+    client_id: u16,
+    transaction_id: u32,
+    /// Signed amount, positive for credits and negative for debits
+    amount: Decimal,
+    reason: String,
+    currency: Currency,
+    timestamp: Option<Timestamp>,
+}
+
+impl Adjustment {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Adjustment {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let scale = record.currency.unwrap_or_default().scale();
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Adjustment,
+                client,
+                tx,
+                amount: Some(amount),
+                to: None,
+                reason: Some(reason),
+                currency,
+                to_currency: None,
+                timestamp,
+            } if !amount.is_zero() && amount.scale() <= scale && !reason.is_empty() => {
+                Ok(Adjustment {
+                    client_id: client,
+                    transaction_id: tx,
+                    amount,
+                    reason,
+                    currency: currency.unwrap_or_default(),
+                    timestamp,
+                })
+            }
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn make_record(amount: Option<Decimal>, reason: Option<&str>) -> TransactionRecord {
+        TransactionRecord {
+            tx_type: TransactionType::Adjustment,
+            client: 1,
+            tx: 1,
+            amount,
+            to: None,
+            reason: reason.map(String::from),
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_credit_and_debit_adjustments() {
+        let credit = Adjustment::try_from(make_record(Some(dec!(12.5)), Some("FEE_REVERSAL")));
+        assert_eq!(credit.unwrap().amount(), dec!(12.5));
+
+        let debit = Adjustment::try_from(make_record(Some(dec!(-3)), Some("DUP_CREDIT"))).unwrap();
+        assert_eq!(debit.client_id(), 1);
+        assert_eq!(debit.transaction_id(), 1);
+        assert_eq!(debit.amount(), dec!(-3));
+        assert_eq!(debit.reason(), "DUP_CREDIT");
+    }
+
+    #[test]
+    fn test_rejects_missing_reason() {
+        assert!(Adjustment::try_from(make_record(Some(dec!(10)), None)).is_err());
+        assert!(Adjustment::try_from(make_record(Some(dec!(10)), Some(""))).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_amount() {
+        for amount in [None, Some(Decimal::ZERO), Some(dec!(1.23456))] {
+            assert!(Adjustment::try_from(make_record(amount, Some("FIX"))).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Deposit,
+            client: 1,
+            tx: 1,
+            amount: Some(dec!(10)),
+            to: None,
+            reason: Some("FIX".to_string()),
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Adjustment::try_from(record).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+    Decimal,
+};
+
+/// A validated authorize transaction, the first phase of a two-phase withdrawal.
+///
+/// Authorizations move funds from the client's available funds into authorized withdrawals,
+/// total funds remain the same until the authorization is captured or voided.
+/// If the client does not have sufficient available funds, the authorization should fail.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Authorize {
+    client_id: u16,
+    transaction_id: u32,
+    amount: Decimal,
+    currency: Currency,
+    timestamp: Option<Timestamp>,
+}
+
+impl Authorize {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Authorize {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let scale = record.currency.unwrap_or_default().scale();
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Authorize,
+                client,
+                tx,
+                amount: Some(amount),
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount > Decimal::ZERO && amount.scale() <= scale => Ok(Authorize {
+                client_id: client,
+                transaction_id: tx,
+                amount,
+                currency: currency.unwrap_or_default(),
+                timestamp,
+            }),
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn make_record(amount: Option<Decimal>) -> TransactionRecord {
+        TransactionRecord {
+            tx_type: TransactionType::Authorize,
+            client: 1,
+            tx: 1,
+            amount,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_authorize() {
+        let record = make_record(Some(dec!(50.25)));
+        let authorize = Authorize::try_from(record).unwrap();
+
+        assert_eq!(authorize.client_id(), 1);
+        assert_eq!(authorize.transaction_id(), 1);
+        assert_eq!(authorize.amount(), dec!(50.25));
+    }
+
+    #[test]
+    fn test_rejects_invalid_amount() {
+        for amount in [
+            None,
+            Some(Decimal::ZERO),
+            Some(dec!(-100)),
+            Some(dec!(1.23456)),
+        ] {
+            assert!(Authorize::try_from(make_record(amount)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Withdrawal,
+            client: 1,
+            tx: 1,
+            amount: Some(dec!(100)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Authorize::try_from(record).is_err());
+    }
+}
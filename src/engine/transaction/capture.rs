@@ -0,0 +1,121 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+};
+
+/// A validated capture transaction, the second phase of a two-phase withdrawal.
+///
+/// Finalizes a pending authorization, removing the authorized funds from total funds, the money
+/// leaves the account. Captures reference the authorization by ID and do not specify an amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Capture {
+    client_id: u16,
+    /// The ID of the authorization being captured
+    referenced_tx_id: u32,
+    /// Currency of the row, `None` books it in the currency of the referenced transaction
+    currency: Option<Currency>,
+    timestamp: Option<Timestamp>,
+}
+
+impl Capture {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn referenced_tx_id(&self) -> u32 {
+        self.referenced_tx_id
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Capture {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Capture,
+                client,
+                tx,
+                amount: None,
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } => Ok(Capture {
+                client_id: client,
+                referenced_tx_id: tx,
+                currency,
+                timestamp,
+            }),
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_valid_capture() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Capture,
+            client: 1,
+            tx: 5,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        let capture = Capture::try_from(record).unwrap();
+
+        assert_eq!(capture.client_id(), 1);
+        assert_eq!(capture.referenced_tx_id(), 5);
+    }
+
+    #[test]
+    fn test_rejects_with_amount() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Capture,
+            client: 1,
+            tx: 5,
+            amount: Some(dec!(100)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Capture::try_from(record).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Resolve,
+            client: 1,
+            tx: 5,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Capture::try_from(record).is_err());
+    }
+}
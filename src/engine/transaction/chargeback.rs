@@ -1,6 +1,9 @@
 use crate::engine::{
+    currency::Currency,
     error::TransactionError,
+    timestamp::Timestamp,
     transaction::{TransactionRecord, TransactionType},
+    Decimal,
 };
 
 /// A validated chargeback transaction.
@@ -8,15 +11,61 @@ use crate::engine::{
 /// A chargeback is the final state of a dispute and represents the client reversing a transaction.
 /// Held funds and total funds decrease by the disputed amount.
 /// The client's account is immediately frozen (locked).
-/// Chargebacks reference the disputed transaction by ID and do not specify an amount.
-#[derive(Debug, Clone)]
+/// Chargebacks reference the disputed transaction by ID and may specify a partial amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Chargeback {
     client_id: u16,
     /// The ID of the transaction being charged back
     referenced_tx_id: u32,
+    /// The partial amount being charged back, `None` for everything that is eligible
+    amount: Option<Decimal>,
+    /// Currency of the row, `None` books it in the currency of the referenced transaction
+    currency: Option<Currency>,
+    timestamp: Option<Timestamp>,
 }
 
 impl Chargeback {
+    /// Create a chargeback of the referenced transaction, validated like a CSV row:
+    /// a partial `amount` must be positive and fit the currency's scale, `None` means charging
+    /// back everything that is eligible. Without a `currency` the chargeback is booked in the
+    /// currency of the referenced transaction.
+    pub fn new(
+        client_id: u16,
+        referenced_tx_id: u32,
+        amount: Option<Decimal>,
+        currency: Option<Currency>,
+        timestamp: Option<Timestamp>,
+    ) -> Result<Self, TransactionError> {
+        Self::try_from(TransactionRecord {
+            tx_type: TransactionType::Chargeback,
+            client: client_id,
+            tx: referenced_tx_id,
+            amount,
+            to: None,
+            reason: None,
+            currency,
+            to_currency: None,
+            timestamp,
+        })
+    }
+
+    /// Create a chargeback charging back everything disputed on the referenced transaction,
+    /// used by the engine to settle expired disputes
+    pub(crate) fn full(
+        client_id: u16,
+        referenced_tx_id: u32,
+        currency: Currency,
+        timestamp: Option<Timestamp>,
+    ) -> Self {
+        Self {
+            client_id,
+            referenced_tx_id,
+            amount: None,
+            currency: Some(currency),
+            timestamp,
+        }
+    }
+
     pub fn client_id(&self) -> u16 {
         self.client_id
     }
@@ -24,22 +73,48 @@ impl Chargeback {
     pub fn referenced_tx_id(&self) -> u32 {
         self.referenced_tx_id
     }
+
+    pub fn amount(&self) -> Option<Decimal> {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
 
 impl TryFrom<TransactionRecord> for Chargeback {
     type Error = TransactionError;
 
     fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        // Without a currency the amount is checked against the referenced transaction's scale
+        let scale = record
+            .currency
+            .map_or(Currency::MAX_SCALE, |currency| currency.scale());
         match record {
             TransactionRecord {
                 tx_type: TransactionType::Chargeback,
                 client,
                 tx,
-                amount: None,
-            } => Ok(Chargeback {
-                client_id: client,
-                referenced_tx_id: tx,
-            }),
+                amount,
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount.is_none_or(|amount| amount > Decimal::ZERO && amount.scale() <= scale) => {
+                Ok(Chargeback {
+                    client_id: client,
+                    referenced_tx_id: tx,
+                    amount,
+                    currency,
+                    timestamp,
+                })
+            }
             _ => Err(TransactionError::InvalidTransaction(record)),
         }
     }
@@ -57,6 +132,11 @@ mod tests {
             client: 3,
             tx: 10,
             amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         let chargeback = Chargeback::try_from(record).unwrap();
 
@@ -65,14 +145,39 @@ mod tests {
     }
 
     #[test]
-    fn test_rejects_with_amount() {
+    fn test_valid_partial_chargeback() {
         let record = TransactionRecord {
             tx_type: TransactionType::Chargeback,
             client: 1,
             tx: 5,
-            amount: Some(dec!(100)),
+            amount: Some(dec!(40.5)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
-        assert!(Chargeback::try_from(record).is_err());
+        let chargeback = Chargeback::try_from(record).unwrap();
+
+        assert_eq!(chargeback.amount(), Some(dec!(40.5)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_partial_amount() {
+        for amount in [Decimal::ZERO, dec!(-100), dec!(1.234567891)] {
+            let record = TransactionRecord {
+                tx_type: TransactionType::Chargeback,
+                client: 1,
+                tx: 5,
+                amount: Some(amount),
+                to: None,
+                reason: None,
+                currency: None,
+                to_currency: None,
+                timestamp: None,
+            };
+            assert!(Chargeback::try_from(record).is_err());
+        }
     }
 
     #[test]
@@ -82,6 +187,11 @@ mod tests {
             client: 1,
             tx: 5,
             amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         assert!(Chargeback::try_from(record).is_err());
     }
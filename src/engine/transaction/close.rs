@@ -0,0 +1,114 @@
+use crate::engine::{
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+};
+
+/// A validated close transaction.
+///
+/// An administrative action that permanently closes a client's account.
+/// Only accounts without any funds can be closed, closed accounts reject every transaction.
+/// Closes carry their own transaction ID and do not specify an amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Close {
+    client_id: u16,
+    transaction_id: u32,
+    timestamp: Option<Timestamp>,
+}
+
+impl Close {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Close {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Close,
+                client,
+                tx,
+                amount: None,
+                to: None,
+                reason: None,
+                // Applies to every currency of the client, the column is ignored
+                currency: _,
+                to_currency: None,
+                timestamp,
+            } => Ok(Close {
+                client_id: client,
+                transaction_id: tx,
+                timestamp,
+            }),
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_valid_close() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Close,
+            client: 1,
+            tx: 7,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        let close = Close::try_from(record).unwrap();
+
+        assert_eq!(close.client_id(), 1);
+        assert_eq!(close.transaction_id(), 7);
+    }
+
+    #[test]
+    fn test_rejects_with_amount() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Close,
+            client: 1,
+            tx: 7,
+            amount: Some(dec!(100)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Close::try_from(record).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Dispute,
+            client: 1,
+            tx: 7,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Close::try_from(record).is_err());
+    }
+}
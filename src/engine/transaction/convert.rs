@@ -0,0 +1,140 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+    Decimal,
+};
+
+/// A validated currency conversion.
+///
+/// Conversions exchange funds between two accounts of the same client: `amount` is debited
+/// in `currency` and the converted amount is credited in `target_currency` (`to_currency`
+/// column), at the rate in effect when the row is processed.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Convert {
+    client_id: u16,
+    transaction_id: u32,
+    amount: Decimal,
+    currency: Currency,
+    target_currency: Currency,
+    timestamp: Option<Timestamp>,
+}
+
+impl Convert {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    /// Returns the amount debited in the source currency
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    /// Returns the source currency
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Returns the currency the funds are exchanged into
+    pub fn target_currency(&self) -> Currency {
+        self.target_currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Convert {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let currency = record.currency.unwrap_or_default();
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Convert,
+                client,
+                tx,
+                amount: Some(amount),
+                to: None,
+                reason: None,
+                to_currency: Some(target_currency),
+                timestamp,
+                ..
+            } if amount > Decimal::ZERO
+                && amount.scale() <= currency.scale()
+                && target_currency != currency =>
+            {
+                Ok(Convert {
+                    client_id: client,
+                    transaction_id: tx,
+                    amount,
+                    currency,
+                    target_currency,
+                    timestamp,
+                })
+            }
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn make_record(amount: Option<Decimal>, to_currency: Option<&str>) -> TransactionRecord {
+        TransactionRecord {
+            tx_type: TransactionType::Convert,
+            client: 1,
+            tx: 3,
+            amount,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: to_currency.map(|code| code.parse().unwrap()),
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_convert() {
+        let record = make_record(Some(dec!(100)), Some("EUR"));
+        let convert = Convert::try_from(record).unwrap();
+
+        assert_eq!(convert.client_id(), 1);
+        assert_eq!(convert.transaction_id(), 3);
+        assert_eq!(convert.amount(), dec!(100));
+        assert_eq!(convert.currency(), Currency::default());
+        assert_eq!(convert.target_currency().code(), "EUR");
+    }
+
+    #[test]
+    fn test_rejects_missing_or_same_target_currency() {
+        for to_currency in [None, Some("USD")] {
+            let record = make_record(Some(dec!(100)), to_currency);
+            assert!(Convert::try_from(record).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_amount() {
+        for amount in [None, Some(dec!(0)), Some(dec!(-1)), Some(dec!(1.00001))] {
+            let record = make_record(amount, Some("EUR"));
+            assert!(Convert::try_from(record).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let mut record = make_record(Some(dec!(100)), Some("EUR"));
+        record.tx_type = TransactionType::Transfer;
+        assert!(Convert::try_from(record).is_err());
+    }
+}
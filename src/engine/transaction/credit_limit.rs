@@ -0,0 +1,121 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+    Decimal,
+};
+
+/// A validated credit limit transaction.
+///
+/// An administrative action that sets the client's credit limit: debits may take available
+/// funds down to `-limit`. A limit of zero removes the overdraft.
+/// Credit limits carry their own transaction ID and the new limit as amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct CreditLimit {
+    client_id: u16,
+    transaction_id: u32,
+    limit: Decimal,
+    currency: Currency,
+    timestamp: Option<Timestamp>,
+}
+
+impl CreditLimit {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn limit(&self) -> Decimal {
+        self.limit
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for CreditLimit {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let scale = record.currency.unwrap_or_default().scale();
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::CreditLimit,
+                client,
+                tx,
+                amount: Some(limit),
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if !limit.is_sign_negative() && limit.scale() <= scale => Ok(CreditLimit {
+                client_id: client,
+                transaction_id: tx,
+                limit,
+                currency: currency.unwrap_or_default(),
+                timestamp,
+            }),
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn make_record(tx_type: TransactionType, amount: Option<Decimal>) -> TransactionRecord {
+        TransactionRecord {
+            tx_type,
+            client: 1,
+            tx: 7,
+            amount,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_credit_limit() {
+        let record = make_record(TransactionType::CreditLimit, Some(dec!(500)));
+        let credit_limit = CreditLimit::try_from(record).unwrap();
+
+        assert_eq!(credit_limit.client_id(), 1);
+        assert_eq!(credit_limit.transaction_id(), 7);
+        assert_eq!(credit_limit.limit(), dec!(500));
+    }
+
+    #[test]
+    fn test_zero_limit_removes_overdraft() {
+        let record = make_record(TransactionType::CreditLimit, Some(dec!(0)));
+        assert_eq!(CreditLimit::try_from(record).unwrap().limit(), dec!(0));
+    }
+
+    #[test]
+    fn test_rejects_invalid_limit() {
+        for amount in [None, Some(dec!(-1)), Some(dec!(1.00001))] {
+            let record = make_record(TransactionType::CreditLimit, amount);
+            assert!(CreditLimit::try_from(record).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = make_record(TransactionType::Deposit, Some(dec!(500)));
+        assert!(CreditLimit::try_from(record).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::engine::{
+    currency::Currency,
     error::TransactionError,
+    timestamp::Timestamp,
     transaction::{TransactionRecord, TransactionType},
     Decimal,
 };
@@ -8,14 +10,38 @@ use crate::engine::{
 ///
 /// Deposits credit the client's account, increasing available and total funds.
 /// Tracks dispute state for dispute/resolve/chargeback flow.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Deposit {
     client_id: u16,
     transaction_id: u32,
     amount: Decimal,
+    currency: Currency,
+    timestamp: Option<Timestamp>,
 }
 
 impl Deposit {
+    /// Create a deposit of `amount` in `currency`, validated like a CSV row:
+    /// the amount must be positive and fit the currency's scale.
+    pub fn new(
+        client_id: u16,
+        transaction_id: u32,
+        amount: Decimal,
+        currency: Currency,
+        timestamp: Option<Timestamp>,
+    ) -> Result<Self, TransactionError> {
+        Self::try_from(TransactionRecord {
+            tx_type: TransactionType::Deposit,
+            client: client_id,
+            tx: transaction_id,
+            amount: Some(amount),
+            to: None,
+            reason: None,
+            currency: Some(currency),
+            to_currency: None,
+            timestamp,
+        })
+    }
+
     pub fn client_id(&self) -> u16 {
         self.client_id
     }
@@ -27,22 +53,38 @@ impl Deposit {
     pub fn amount(&self) -> Decimal {
         self.amount
     }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
 
 impl TryFrom<TransactionRecord> for Deposit {
     type Error = TransactionError;
 
     fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let scale = record.currency.unwrap_or_default().scale();
         match record {
             TransactionRecord {
                 tx_type: TransactionType::Deposit,
                 client,
                 tx,
                 amount: Some(amount),
-            } if amount > Decimal::ZERO && amount.scale() <= 4 => Ok(Deposit {
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount > Decimal::ZERO && amount.scale() <= scale => Ok(Deposit {
                 client_id: client,
                 transaction_id: tx,
                 amount,
+                currency: currency.unwrap_or_default(),
+                timestamp,
             }),
             _ => Err(TransactionError::InvalidTransaction(record)),
         }
@@ -60,6 +102,11 @@ mod tests {
             client: 1,
             tx: 1,
             amount,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         }
     }
 
@@ -111,7 +158,23 @@ mod tests {
             client: 1,
             tx: 1,
             amount: Some(dec!(100)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         assert!(Deposit::try_from(record).is_err());
     }
+
+    #[test]
+    fn test_new_validates_like_a_row() {
+        let jpy: Currency = "JPY".parse().unwrap();
+        let deposit = Deposit::new(1, 2, dec!(500), jpy, None).unwrap();
+        assert_eq!(deposit.transaction_id(), 2);
+        assert_eq!(deposit.currency(), jpy);
+
+        assert!(Deposit::new(1, 2, dec!(0.5), jpy, None).is_err());
+        assert!(Deposit::new(1, 2, dec!(-1), Currency::default(), None).is_err());
+    }
 }
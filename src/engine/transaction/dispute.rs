@@ -1,21 +1,53 @@
 use crate::engine::{
+    currency::Currency,
     error::TransactionError,
+    timestamp::Timestamp,
     transaction::{TransactionRecord, TransactionType},
+    Decimal,
 };
 
 /// A validated dispute transaction.
 ///
 /// A dispute represents a client's claim that a transaction was erroneous.
 /// The disputed funds are held (moved from available to held) while total remains the same.
-/// Disputes reference the original transaction by ID and do not specify an amount.
-#[derive(Debug, Clone)]
+/// Disputes reference the original transaction by ID and may specify a partial amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Dispute {
     client_id: u16,
     /// The ID of the transaction being disputed
     referenced_tx_id: u32,
+    /// The partial amount being disputed, `None` for everything that is eligible
+    amount: Option<Decimal>,
+    /// Currency of the row, `None` books it in the currency of the referenced transaction
+    currency: Option<Currency>,
+    timestamp: Option<Timestamp>,
 }
 
 impl Dispute {
+    /// Create a dispute of the referenced transaction, validated like a CSV row:
+    /// a partial `amount` must be positive and fit the currency's scale, `None` means disputing
+    /// everything that is eligible. Without a `currency` the dispute is booked in the currency of
+    /// the referenced transaction.
+    pub fn new(
+        client_id: u16,
+        referenced_tx_id: u32,
+        amount: Option<Decimal>,
+        currency: Option<Currency>,
+        timestamp: Option<Timestamp>,
+    ) -> Result<Self, TransactionError> {
+        Self::try_from(TransactionRecord {
+            tx_type: TransactionType::Dispute,
+            client: client_id,
+            tx: referenced_tx_id,
+            amount,
+            to: None,
+            reason: None,
+            currency,
+            to_currency: None,
+            timestamp,
+        })
+    }
+
     pub fn client_id(&self) -> u16 {
         self.client_id
     }
@@ -23,22 +55,48 @@ impl Dispute {
     pub fn referenced_tx_id(&self) -> u32 {
         self.referenced_tx_id
     }
+
+    pub fn amount(&self) -> Option<Decimal> {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
 
 impl TryFrom<TransactionRecord> for Dispute {
     type Error = TransactionError;
 
     fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        // Without a currency the amount is checked against the referenced transaction's scale
+        let scale = record
+            .currency
+            .map_or(Currency::MAX_SCALE, |currency| currency.scale());
         match record {
             TransactionRecord {
                 tx_type: TransactionType::Dispute,
                 client,
                 tx,
-                amount: None,
-            } => Ok(Dispute {
-                client_id: client,
-                referenced_tx_id: tx,
-            }),
+                amount,
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount.is_none_or(|amount| amount > Decimal::ZERO && amount.scale() <= scale) => {
+                Ok(Dispute {
+                    client_id: client,
+                    referenced_tx_id: tx,
+                    amount,
+                    currency,
+                    timestamp,
+                })
+            }
             _ => Err(TransactionError::InvalidTransaction(record)),
         }
     }
@@ -56,6 +114,11 @@ mod tests {
             client: 1,
             tx: 5,
             amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         let dispute = Dispute::try_from(record).unwrap();
 
@@ -64,14 +127,39 @@ mod tests {
     }
 
     #[test]
-    fn test_rejects_with_amount() {
+    fn test_valid_partial_dispute() {
         let record = TransactionRecord {
             tx_type: TransactionType::Dispute,
             client: 1,
             tx: 5,
-            amount: Some(dec!(100)),
+            amount: Some(dec!(40.5)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
-        assert!(Dispute::try_from(record).is_err());
+        let dispute = Dispute::try_from(record).unwrap();
+
+        assert_eq!(dispute.amount(), Some(dec!(40.5)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_partial_amount() {
+        for amount in [Decimal::ZERO, dec!(-100), dec!(1.234567891)] {
+            let record = TransactionRecord {
+                tx_type: TransactionType::Dispute,
+                client: 1,
+                tx: 5,
+                amount: Some(amount),
+                to: None,
+                reason: None,
+                currency: None,
+                to_currency: None,
+                timestamp: None,
+            };
+            assert!(Dispute::try_from(record).is_err());
+        }
     }
 
     #[test]
@@ -81,7 +169,21 @@ mod tests {
             client: 1,
             tx: 5,
             amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         assert!(Dispute::try_from(record).is_err());
     }
+
+    #[test]
+    fn test_new_validates_like_a_row() {
+        let dispute = Dispute::new(1, 5, None, None, None).unwrap();
+        assert_eq!(dispute.referenced_tx_id(), 5);
+        assert_eq!(dispute.amount(), None);
+
+        assert!(Dispute::new(1, 5, Some(dec!(0)), None, None).is_err());
+    }
 }
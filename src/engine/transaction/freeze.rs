@@ -0,0 +1,129 @@
+use crate::engine::{
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+};
+
+/// A validated freeze transaction.
+///
+/// An administrative action by compliance that proactively freezes (locks) the client's account.
+/// Freezes carry their own transaction ID and do not specify an amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Freeze {
+    client_id: u16,
+    transaction_id: u32,
+    timestamp: Option<Timestamp>,
+}
+
+impl Freeze {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Freeze {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Freeze,
+                client,
+                tx,
+                amount: None,
+                to: None,
+                reason: None,
+                // Applies to every currency of the client, the column is ignored
+                currency: _,
+                to_currency: None,
+                timestamp,
+            } => Ok(Freeze {
+                client_id: client,
+                transaction_id: tx,
+                timestamp,
+            }),
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_valid_freeze() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Freeze,
+            client: 1,
+            tx: 7,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        let freeze = Freeze::try_from(record).unwrap();
+
+        assert_eq!(freeze.client_id(), 1);
+        assert_eq!(freeze.transaction_id(), 7);
+    }
+
+    #[test]
+    fn test_ignores_currency() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Freeze,
+            client: 1,
+            tx: 7,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: Some("EUR".parse().unwrap()),
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Freeze::try_from(record).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_with_amount() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Freeze,
+            client: 1,
+            tx: 7,
+            amount: Some(dec!(100)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Freeze::try_from(record).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Dispute,
+            client: 1,
+            tx: 7,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Freeze::try_from(record).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+    Decimal,
+};
+
+/// A validated refund transaction.
+///
+/// A refund returns part or all of a deposit to the payer,
+/// decreasing the client's available and total funds.
+/// Refunds reference the original deposit by ID and may specify a partial amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Refund {
+    client_id: u16,
+    /// The ID of the deposit being refunded
+    referenced_tx_id: u32,
+    /// The partial amount being refunded, `None` for everything that is eligible
+    amount: Option<Decimal>,
+    /// Currency of the row, `None` books it in the currency of the referenced transaction
+    currency: Option<Currency>,
+    timestamp: Option<Timestamp>,
+}
+
+impl Refund {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn referenced_tx_id(&self) -> u32 {
+        self.referenced_tx_id
+    }
+
+    pub fn amount(&self) -> Option<Decimal> {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Refund {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        // Without a currency the amount is checked against the referenced transaction's scale
+        let scale = record
+            .currency
+            .map_or(Currency::MAX_SCALE, |currency| currency.scale());
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Refund,
+                client,
+                tx,
+                amount,
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount.is_none_or(|amount| amount > Decimal::ZERO && amount.scale() <= scale) => {
+                Ok(Refund {
+                    client_id: client,
+                    referenced_tx_id: tx,
+                    amount,
+                    currency,
+                    timestamp,
+                })
+            }
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_valid_refund() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Refund,
+            client: 1,
+            tx: 5,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        let refund = Refund::try_from(record).unwrap();
+
+        assert_eq!(refund.client_id(), 1);
+        assert_eq!(refund.referenced_tx_id(), 5);
+    }
+
+    #[test]
+    fn test_valid_partial_refund() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Refund,
+            client: 1,
+            tx: 5,
+            amount: Some(dec!(40.5)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        let refund = Refund::try_from(record).unwrap();
+
+        assert_eq!(refund.amount(), Some(dec!(40.5)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_partial_amount() {
+        for amount in [Decimal::ZERO, dec!(-100), dec!(1.234567891)] {
+            let record = TransactionRecord {
+                tx_type: TransactionType::Refund,
+                client: 1,
+                tx: 5,
+                amount: Some(amount),
+                to: None,
+                reason: None,
+                currency: None,
+                to_currency: None,
+                timestamp: None,
+            };
+            assert!(Refund::try_from(record).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Deposit,
+            client: 1,
+            tx: 5,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Refund::try_from(record).is_err());
+    }
+}
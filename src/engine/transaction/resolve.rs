@@ -1,6 +1,9 @@
 use crate::engine::{
+    currency::Currency,
     error::TransactionError,
+    timestamp::Timestamp,
     transaction::{TransactionRecord, TransactionType},
+    Decimal,
 };
 
 /// A validated resolve transaction.
@@ -8,15 +11,61 @@ use crate::engine::{
 /// A resolve represents a resolution to a dispute, releasing the held funds.
 /// Held funds decrease and available funds increase by the disputed amount.
 /// Total funds remain the same.
-/// Resolves reference the disputed transaction by ID and do not specify an amount.
-#[derive(Debug, Clone)]
+/// Resolves reference the disputed transaction by ID and may specify a partial amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Resolve {
     client_id: u16,
     /// The ID of the transaction being resolved
     referenced_tx_id: u32,
+    /// The partial amount being resolved, `None` for everything that is eligible
+    amount: Option<Decimal>,
+    /// Currency of the row, `None` books it in the currency of the referenced transaction
+    currency: Option<Currency>,
+    timestamp: Option<Timestamp>,
 }
 
 impl Resolve {
+    /// Create a resolve of the referenced transaction, validated like a CSV row:
+    /// a partial `amount` must be positive and fit the currency's scale, `None` means resolving
+    /// everything that is eligible. Without a `currency` the resolve is booked in the currency of
+    /// the referenced transaction.
+    pub fn new(
+        client_id: u16,
+        referenced_tx_id: u32,
+        amount: Option<Decimal>,
+        currency: Option<Currency>,
+        timestamp: Option<Timestamp>,
+    ) -> Result<Self, TransactionError> {
+        Self::try_from(TransactionRecord {
+            tx_type: TransactionType::Resolve,
+            client: client_id,
+            tx: referenced_tx_id,
+            amount,
+            to: None,
+            reason: None,
+            currency,
+            to_currency: None,
+            timestamp,
+        })
+    }
+
+    /// Create a resolve resolving everything disputed on the referenced transaction,
+    /// used by the engine to settle expired disputes
+    pub(crate) fn full(
+        client_id: u16,
+        referenced_tx_id: u32,
+        currency: Currency,
+        timestamp: Option<Timestamp>,
+    ) -> Self {
+        Self {
+            client_id,
+            referenced_tx_id,
+            amount: None,
+            currency: Some(currency),
+            timestamp,
+        }
+    }
+
     pub fn client_id(&self) -> u16 {
         self.client_id
     }
@@ -24,22 +73,48 @@ impl Resolve {
     pub fn referenced_tx_id(&self) -> u32 {
         self.referenced_tx_id
     }
+
+    pub fn amount(&self) -> Option<Decimal> {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
 
 impl TryFrom<TransactionRecord> for Resolve {
     type Error = TransactionError;
 
     fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        // Without a currency the amount is checked against the referenced transaction's scale
+        let scale = record
+            .currency
+            .map_or(Currency::MAX_SCALE, |currency| currency.scale());
         match record {
             TransactionRecord {
                 tx_type: TransactionType::Resolve,
                 client,
                 tx,
-                amount: None,
-            } => Ok(Resolve {
-                client_id: client,
-                referenced_tx_id: tx,
-            }),
+                amount,
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount.is_none_or(|amount| amount > Decimal::ZERO && amount.scale() <= scale) => {
+                Ok(Resolve {
+                    client_id: client,
+                    referenced_tx_id: tx,
+                    amount,
+                    currency,
+                    timestamp,
+                })
+            }
             _ => Err(TransactionError::InvalidTransaction(record)),
         }
     }
@@ -57,6 +132,11 @@ mod tests {
             client: 2,
             tx: 5,
             amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         let resolve = Resolve::try_from(record).unwrap();
 
@@ -65,14 +145,39 @@ mod tests {
     }
 
     #[test]
-    fn test_rejects_with_amount() {
+    fn test_valid_partial_resolve() {
         let record = TransactionRecord {
             tx_type: TransactionType::Resolve,
             client: 1,
             tx: 5,
-            amount: Some(dec!(100)),
+            amount: Some(dec!(40.5)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
-        assert!(Resolve::try_from(record).is_err());
+        let resolve = Resolve::try_from(record).unwrap();
+
+        assert_eq!(resolve.amount(), Some(dec!(40.5)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_partial_amount() {
+        for amount in [Decimal::ZERO, dec!(-100), dec!(1.234567891)] {
+            let record = TransactionRecord {
+                tx_type: TransactionType::Resolve,
+                client: 1,
+                tx: 5,
+                amount: Some(amount),
+                to: None,
+                reason: None,
+                currency: None,
+                to_currency: None,
+                timestamp: None,
+            };
+            assert!(Resolve::try_from(record).is_err());
+        }
     }
 
     #[test]
@@ -82,6 +187,11 @@ mod tests {
             client: 1,
             tx: 5,
             amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         assert!(Resolve::try_from(record).is_err());
     }
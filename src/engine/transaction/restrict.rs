@@ -0,0 +1,121 @@
+use crate::engine::{
+    error::TransactionError,
+    status::Restriction,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+};
+
+/// A validated restriction transaction.
+///
+/// An administrative action that limits which transactions an account accepts:
+/// `withdraw_only` and `deposit_only` restrict the account, `activate` lifts the restriction.
+/// Freezes are not affected, use `unlock`/`unfreeze` for those.
+/// Restrictions carry their own transaction ID and do not specify an amount.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Restrict {
+    client_id: u16,
+    transaction_id: u32,
+    restriction: Restriction,
+    timestamp: Option<Timestamp>,
+}
+
+impl Restrict {
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn restriction(&self) -> Restriction {
+        self.restriction
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Restrict {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let restriction = match record.tx_type {
+            TransactionType::WithdrawOnly => Restriction::WithdrawOnly,
+            TransactionType::DepositOnly => Restriction::DepositOnly,
+            TransactionType::Activate => Restriction::None,
+            _ => return Err(TransactionError::InvalidTransaction(record)),
+        };
+
+        match record {
+            TransactionRecord {
+                client,
+                tx,
+                amount: None,
+                to: None,
+                reason: None,
+                // Applies to every currency of the client, the column is ignored
+                currency: _,
+                to_currency: None,
+                timestamp,
+                ..
+            } => Ok(Restrict {
+                client_id: client,
+                transaction_id: tx,
+                restriction,
+                timestamp,
+            }),
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn make_record(tx_type: TransactionType) -> TransactionRecord {
+        TransactionRecord {
+            tx_type,
+            client: 1,
+            tx: 7,
+            amount: None,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_restrictions() {
+        let cases = [
+            (TransactionType::WithdrawOnly, Restriction::WithdrawOnly),
+            (TransactionType::DepositOnly, Restriction::DepositOnly),
+            (TransactionType::Activate, Restriction::None),
+        ];
+
+        for (tx_type, expected) in cases {
+            let restrict = Restrict::try_from(make_record(tx_type)).unwrap();
+            assert_eq!(restrict.client_id(), 1);
+            assert_eq!(restrict.transaction_id(), 7);
+            assert_eq!(restrict.restriction(), expected);
+        }
+    }
+
+    #[test]
+    fn test_rejects_with_amount() {
+        let mut record = make_record(TransactionType::WithdrawOnly);
+        record.amount = Some(dec!(100));
+        assert!(Restrict::try_from(record).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = make_record(TransactionType::Freeze);
+        assert!(Restrict::try_from(record).is_err());
+    }
+}
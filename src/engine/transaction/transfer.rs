@@ -0,0 +1,153 @@
+use crate::engine::{
+    currency::Currency,
+    error::TransactionError,
+    timestamp::Timestamp,
+    transaction::{TransactionRecord, TransactionType},
+    Decimal,
+};
+
+/// A validated client-to-client transfer.
+///
+/// Transfers debit the sending client (`client` column) and credit the receiving client
+/// (`to` column) in one step, either both sides are applied or neither is.
+/// Like deposits, transfers can be disputed by the sending client.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Transfer {
+    client_id: u16,
+    recipient_id: u16,
+    transaction_id: u32,
+    amount: Decimal,
+    currency: Currency,
+    timestamp: Option<Timestamp>,
+}
+
+impl Transfer {
+    /// Returns the sending client
+    pub fn client_id(&self) -> u16 {
+        self.client_id
+    }
+
+    /// Returns the receiving client
+    pub fn recipient_id(&self) -> u16 {
+        self.recipient_id
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+impl TryFrom<TransactionRecord> for Transfer {
+    type Error = TransactionError;
+
+    fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let scale = record.currency.unwrap_or_default().scale();
+        match record {
+            TransactionRecord {
+                tx_type: TransactionType::Transfer,
+                client,
+                tx,
+                amount: Some(amount),
+                to: Some(to),
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount > Decimal::ZERO && amount.scale() <= scale && to != client => {
+                Ok(Transfer {
+                    client_id: client,
+                    recipient_id: to,
+                    transaction_id: tx,
+                    amount,
+                    currency: currency.unwrap_or_default(),
+                    timestamp,
+                })
+            }
+            _ => Err(TransactionError::InvalidTransaction(record)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn make_record(amount: Option<Decimal>, to: Option<u16>) -> TransactionRecord {
+        TransactionRecord {
+            tx_type: TransactionType::Transfer,
+            client: 1,
+            tx: 1,
+            amount,
+            to,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_transfer() {
+        let record = make_record(Some(dec!(25.5)), Some(2));
+        let transfer = Transfer::try_from(record).unwrap();
+
+        assert_eq!(transfer.client_id(), 1);
+        assert_eq!(transfer.recipient_id(), 2);
+        assert_eq!(transfer.transaction_id(), 1);
+        assert_eq!(transfer.amount(), dec!(25.5));
+    }
+
+    #[test]
+    fn test_rejects_missing_recipient() {
+        let record = make_record(Some(dec!(25)), None);
+        assert!(Transfer::try_from(record).is_err());
+    }
+
+    #[test]
+    fn test_rejects_transfer_to_self() {
+        let record = make_record(Some(dec!(25)), Some(1));
+        assert!(Transfer::try_from(record).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_amount() {
+        for amount in [
+            None,
+            Some(Decimal::ZERO),
+            Some(dec!(-5)),
+            Some(dec!(1.23456)),
+        ] {
+            let record = make_record(amount, Some(2));
+            assert!(Transfer::try_from(record).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_transaction_type() {
+        let record = TransactionRecord {
+            tx_type: TransactionType::Deposit,
+            client: 1,
+            tx: 1,
+            amount: Some(dec!(25)),
+            to: Some(2),
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
+        };
+        assert!(Transfer::try_from(record).is_err());
+    }
+}
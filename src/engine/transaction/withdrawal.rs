@@ -1,5 +1,7 @@
 use crate::engine::{
+    currency::Currency,
     error::TransactionError,
+    timestamp::Timestamp,
     transaction::{TransactionRecord, TransactionType},
     Decimal,
 };
@@ -8,19 +10,42 @@ use crate::engine::{
 ///
 /// Withdrawals debit the client's account, decreasing available and total funds.
 /// If the client does not have sufficient available funds, the withdrawal should fail.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Withdrawal {
     client_id: u16,
     transaction_id: u32,
     amount: Decimal,
+    currency: Currency,
+    timestamp: Option<Timestamp>,
 }
 
 impl Withdrawal {
+    /// Create a withdrawal of `amount` in `currency`, validated like a CSV row:
+    /// the amount must be positive and fit the currency's scale.
+    pub fn new(
+        client_id: u16,
+        transaction_id: u32,
+        amount: Decimal,
+        currency: Currency,
+        timestamp: Option<Timestamp>,
+    ) -> Result<Self, TransactionError> {
+        Self::try_from(TransactionRecord {
+            tx_type: TransactionType::Withdrawal,
+            client: client_id,
+            tx: transaction_id,
+            amount: Some(amount),
+            to: None,
+            reason: None,
+            currency: Some(currency),
+            to_currency: None,
+            timestamp,
+        })
+    }
+
     pub fn client_id(&self) -> u16 {
         self.client_id
     }
 
-    #[allow(unused)]
     pub fn transaction_id(&self) -> u32 {
         self.transaction_id
     }
@@ -28,22 +53,38 @@ impl Withdrawal {
     pub fn amount(&self) -> Decimal {
         self.amount
     }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
 
 impl TryFrom<TransactionRecord> for Withdrawal {
     type Error = TransactionError;
 
     fn try_from(record: TransactionRecord) -> Result<Self, Self::Error> {
+        let scale = record.currency.unwrap_or_default().scale();
         match record {
             TransactionRecord {
                 tx_type: TransactionType::Withdrawal,
                 client,
                 tx,
                 amount: Some(amount),
-            } if amount > Decimal::ZERO && amount.scale() <= 4 => Ok(Withdrawal {
+                to: None,
+                reason: None,
+                currency,
+                to_currency: None,
+                timestamp,
+            } if amount > Decimal::ZERO && amount.scale() <= scale => Ok(Withdrawal {
                 client_id: client,
                 transaction_id: tx,
                 amount,
+                currency: currency.unwrap_or_default(),
+                timestamp,
             }),
             _ => Err(TransactionError::InvalidTransaction(record)),
         }
@@ -61,6 +102,11 @@ mod tests {
             client: 1,
             tx: 1,
             amount,
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         }
     }
 
@@ -112,6 +158,11 @@ mod tests {
             client: 1,
             tx: 1,
             amount: Some(dec!(100)),
+            to: None,
+            reason: None,
+            currency: None,
+            to_currency: None,
+            timestamp: None,
         };
         assert!(Withdrawal::try_from(record).is_err());
     }
@@ -1,5 +1,62 @@
 mod engine;
 
-// re-export PaymentEngine and Account
+// re-export PaymentEngine with its builder, policy, fee schedule and rate table, Account with its
+// status, currency and export format, TransactionState with the RetainedTransaction snapshots,
+// AuthorizationState, the Ledger behind the balances, the typed transactions accepted by
+// PaymentEngine::apply, the errors it returns and the ProcessingReport of process_transactions
 pub use engine::Account;
+pub use engine::AccountStatus;
+pub use engine::Adjustment;
+pub use engine::AuthorizationState;
+pub use engine::Authorize;
+pub use engine::Bucket;
+pub use engine::Capture;
+pub use engine::Chargeback;
+pub use engine::ClientConfig;
+pub use engine::ClientId;
+pub use engine::Close;
+pub use engine::Conversion;
+pub use engine::Convert;
+pub use engine::CreditLimit;
+pub use engine::Currency;
+pub use engine::Deposit;
+pub use engine::Dispute;
+pub use engine::EnginePolicy;
+pub use engine::Error;
+pub use engine::ExchangeRate;
+pub use engine::ExpiredDispute;
+pub use engine::ExpiryAction;
+pub use engine::ExportFormat;
+pub use engine::Fee;
+pub use engine::FeeSchedule;
+pub use engine::FeeTier;
+pub use engine::Freeze;
+pub use engine::FreezeReason;
+pub use engine::JournalEntry;
+pub use engine::Ledger;
+pub use engine::LedgerAccount;
+pub use engine::Outcome;
 pub use engine::PaymentEngine;
+pub use engine::PaymentEngineBuilder;
+pub use engine::Posting;
+pub use engine::ProcessingError;
+pub use engine::ProcessingReport;
+pub use engine::RateTable;
+pub use engine::Refund;
+pub use engine::RejectedRow;
+pub use engine::Resolve;
+pub use engine::Restrict;
+pub use engine::RetainedTransaction;
+pub use engine::Timestamp;
+pub use engine::Transaction;
+pub use engine::TransactionError;
+pub use engine::TransactionId;
+pub use engine::TransactionRecord;
+pub use engine::TransactionState;
+pub use engine::TransactionType;
+pub use engine::Transfer;
+pub use engine::Unfreeze;
+pub use engine::Unlock;
+pub use engine::UnmatchedRow;
+pub use engine::Void;
+pub use engine::Withdrawal;
@@ -1,8 +1,16 @@
 //! Integration tests for the `PaymentEngine`.
 //!
 //! These tests exercise the full E2E flow: CSV input → processing → CSV output.
-use payment_engine::{Account, PaymentEngine};
+use payment_engine::{
+    Account, AccountStatus, AuthorizationState, Chargeback, ClientConfig, Currency, Deposit,
+    Dispute, EnginePolicy, ExpiryAction, ExportFormat, Fee, FeeSchedule, FeeTier, FreezeReason,
+    LedgerAccount, Outcome, PaymentEngine, PaymentEngineBuilder, ProcessingError, ProcessingReport,
+    RateTable, Resolve, RetainedTransaction, Transaction, TransactionState, TransactionType,
+    Withdrawal,
+};
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use std::collections::BTreeSet;
 use std::io::Cursor;
 
 /// Helper to run a transaction CSV through the engine and get output
@@ -12,11 +20,14 @@ fn process_csv(input: &str) -> String {
     engine.process_transactions(reader).unwrap();
 
     let mut output = Vec::new();
-    engine.export_accounts(&mut output).unwrap();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
     String::from_utf8(output).unwrap()
 }
 
-/// Parse CSV output into a vec of (client, available, held, total, locked)
+/// Parse CSV output into a vec of (client, available, held, pending, authorized, total, locked,
+/// status)
 fn parse_output(output: &str) -> Vec<Account> {
     let mut rdr = csv::Reader::from_reader(output.as_bytes());
     rdr.deserialize::<Account>().map(|r| r.unwrap()).collect()
@@ -574,3 +585,2966 @@ chargeback,1,1,";
     assert_eq!(accounts[0].total(), dec!(0));
     assert!(accounts[0].is_locked());
 }
+
+// ============================================================================
+// Duplicate Transaction IDs
+// ============================================================================
+
+/// Helper to run a transaction CSV through an engine configured with `policy`
+fn process_csv_with_policy(input: &str, policy: EnginePolicy) -> String {
+    let mut engine = PaymentEngine::with_policy(policy);
+    engine.process_transactions(Cursor::new(input)).unwrap();
+
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_duplicate_deposit_id_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,1,500.0
+dispute,1,1,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // Second deposit must not overwrite the first one, dispute holds the original amount
+    assert_eq!(accounts[0].available(), dec!(0));
+    assert_eq!(accounts[0].held(), dec!(100));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_withdrawal_reusing_deposit_id_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,1,40.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_deposit_reusing_withdrawal_id_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,40.0
+deposit,1,2,40.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(60));
+}
+
+#[test]
+fn test_failed_transaction_id_can_be_reused() {
+    let input = "type,client,tx,amount
+deposit,1,1,10.0
+withdrawal,1,2,40.0
+withdrawal,1,2,5.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // The first withdrawal failed (insufficient funds) so its ID was never applied
+    assert_eq!(accounts[0].available(), dec!(5));
+}
+
+#[test]
+fn test_identical_replay_rejected_by_default() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,1,100.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(100));
+}
+
+#[test]
+fn test_identical_replay_is_idempotent_when_enabled() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,30.0
+deposit,1,1,100.0
+withdrawal,1,2,30.0
+deposit,1,1,999.0";
+
+    let policy = EnginePolicy {
+        idempotent_replays: true,
+        ..EnginePolicy::default()
+    };
+    let output = process_csv_with_policy(input, policy);
+    let accounts = parse_output(&output);
+
+    // Replays are no-ops, the differing reuse of tx 1 is still rejected
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert_eq!(accounts[0].total(), dec!(70));
+}
+
+#[test]
+fn test_replay_with_other_timestamp_is_rejected() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-03-01T12:00:00Z
+deposit,1,1,100.0,2024-03-01T12:00:00Z
+deposit,1,1,100.0,2024-03-02T12:00:00Z";
+
+    let mut engine = PaymentEngine::builder().idempotent_replays(true).build();
+    let report = engine.process_transactions(Cursor::new(input)).unwrap();
+
+    assert_eq!(report.replayed(), 1);
+    assert_eq!(report.errors_of_kind("duplicate_transaction"), 1);
+}
+
+#[test]
+fn test_replay_matches_amounts_written_with_other_decimal_places() {
+    let input = "type,client,tx,amount
+deposit,1,1,100
+deposit,1,1,100.00
+deposit,1,1,100.01";
+
+    let mut engine = PaymentEngine::builder().idempotent_replays(true).build();
+    let report = engine.process_transactions(Cursor::new(input)).unwrap();
+
+    assert_eq!(report.replayed(), 1);
+    assert_eq!(report.errors_of_kind("duplicate_transaction"), 1);
+}
+
+// ============================================================================
+// Dispute Lifecycle
+// ============================================================================
+
+/// Helper to run a transaction CSV through an engine and return the engine itself
+fn process_engine(input: &str, policy: EnginePolicy) -> PaymentEngine {
+    let mut engine = PaymentEngine::with_policy(policy);
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    engine
+}
+
+#[test]
+fn test_transaction_state_follows_dispute_lifecycle() {
+    let engine = process_engine(
+        "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+deposit,1,3,25.0
+dispute,1,2,
+dispute,1,3,
+resolve,1,3,",
+        EnginePolicy::default(),
+    );
+
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+    assert_eq!(
+        engine.transaction_state(2),
+        Some(TransactionState::Disputed)
+    );
+    assert_eq!(
+        engine.transaction_state(3),
+        Some(TransactionState::Resolved)
+    );
+    assert_eq!(engine.transaction_state(999), None);
+}
+
+#[test]
+fn test_charged_back_transaction_state_is_final() {
+    let engine = process_engine(
+        "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+chargeback,1,1,
+resolve,1,1,
+dispute,1,1,",
+        EnginePolicy::default(),
+    );
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::ChargedBack)
+    );
+}
+
+#[test]
+fn test_redispute_can_be_disabled() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+resolve,1,1,
+dispute,1,1,";
+    let policy = EnginePolicy {
+        allow_redispute: false,
+        ..EnginePolicy::default()
+    };
+
+    let engine = process_engine(input, policy.clone());
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+
+    let accounts = parse_output(&process_csv_with_policy(input, policy));
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].held(), dec!(0));
+}
+
+#[test]
+fn test_undisputed_part_can_be_disputed_when_redispute_is_disabled() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,40.0
+resolve,1,1,
+dispute,1,1,";
+    let policy = EnginePolicy {
+        allow_redispute: false,
+        ..EnginePolicy::default()
+    };
+
+    let engine = process_engine(input, policy);
+    assert_eq!(engine.disputed_amount(1), Some(dec!(60)));
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+}
+
+// ============================================================================
+// Withdrawal Disputes
+// ============================================================================
+
+fn withdrawal_disputes_policy() -> EnginePolicy {
+    EnginePolicy {
+        allow_withdrawal_disputes: true,
+        ..EnginePolicy::default()
+    }
+}
+
+#[test]
+fn test_withdrawal_dispute_is_ignored_by_default() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,40.0
+dispute,1,2,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(60));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(60));
+}
+
+#[test]
+fn test_withdrawal_dispute_holds_reversed_funds() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,40.0
+dispute,1,2,";
+
+    let output = process_csv_with_policy(input, withdrawal_disputes_policy());
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(60));
+    assert_eq!(accounts[0].held(), dec!(40));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_withdrawal_dispute_resolve_upholds_withdrawal() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,40.0
+dispute,1,2,
+resolve,1,2,";
+
+    let output = process_csv_with_policy(input, withdrawal_disputes_policy());
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(60));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(60));
+    assert!(!accounts[0].is_locked());
+}
+
+#[test]
+fn test_withdrawal_chargeback_credits_funds_back() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,40.0
+dispute,1,2,
+chargeback,1,2,";
+
+    let engine = process_engine(input, withdrawal_disputes_policy());
+    assert_eq!(
+        engine.transaction_state(2),
+        Some(TransactionState::ChargedBack)
+    );
+
+    let output = process_csv_with_policy(input, withdrawal_disputes_policy());
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(100));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_withdrawal_dispute_with_wrong_client_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,100.0
+withdrawal,1,3,40.0
+dispute,2,3,";
+
+    let output = process_csv_with_policy(input, withdrawal_disputes_policy());
+    let accounts = parse_output(&output);
+
+    let client1 = accounts.iter().find(|a| a.client_id() == 1).unwrap();
+    let client2 = accounts.iter().find(|a| a.client_id() == 2).unwrap();
+    assert_eq!(client1.held(), dec!(0));
+    assert_eq!(client2.held(), dec!(0));
+}
+
+// ============================================================================
+// Partial Disputes
+// ============================================================================
+
+#[test]
+fn test_partial_dispute_holds_only_disputed_amount() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,30.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert_eq!(accounts[0].held(), dec!(30));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_multiple_partial_disputes_accumulate() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,30.0
+dispute,1,1,50.0
+dispute,1,1,25.0";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    // Third dispute exceeds the remaining 20 and is ignored
+    assert_eq!(engine.disputed_amount(1), Some(dec!(80)));
+
+    let accounts = parse_output(&process_csv(input));
+    assert_eq!(accounts[0].available(), dec!(20));
+    assert_eq!(accounts[0].held(), dec!(80));
+}
+
+#[test]
+fn test_full_dispute_after_partial_dispute_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,30.0
+dispute,1,1,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].held(), dec!(30));
+}
+
+#[test]
+fn test_partial_resolve_and_chargeback() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,60.0
+resolve,1,1,20.0
+chargeback,1,1,15.0";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+    assert_eq!(engine.disputed_amount(1), Some(dec!(25)));
+
+    let accounts = parse_output(&process_csv(input));
+    assert_eq!(accounts[0].available(), dec!(60));
+    assert_eq!(accounts[0].held(), dec!(25));
+    assert_eq!(accounts[0].total(), dec!(85));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_resolve_exceeding_disputed_amount_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,30.0
+resolve,1,1,40.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert_eq!(accounts[0].held(), dec!(30));
+}
+
+#[test]
+fn test_resolve_without_amount_releases_all_partial_disputes() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,30.0
+dispute,1,1,20.0
+resolve,1,1,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+
+    let accounts = parse_output(&process_csv(input));
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].held(), dec!(0));
+}
+
+#[test]
+fn test_rejects_zero_dispute_amount() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,0";
+
+    assert!(try_process_csv(input).is_err());
+}
+
+// ============================================================================
+// Engine Policy
+// ============================================================================
+
+/// Helper to run a transaction CSV through an engine built by `builder`
+fn process_csv_with_builder(input: &str, builder: PaymentEngineBuilder) -> Vec<Account> {
+    let mut engine = builder.build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    parse_output(&String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_policy_rejects_dispute_driving_available_negative() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,80.0
+dispute,1,1,";
+
+    let accounts = process_csv_with_builder(
+        input,
+        PaymentEngine::builder().allow_negative_available_on_dispute(false),
+    );
+
+    assert_eq!(accounts[0].available(), dec!(20));
+    assert_eq!(accounts[0].held(), dec!(0));
+}
+
+#[test]
+fn test_policy_allows_withdrawal_without_account() {
+    let input = "type,client,tx,amount
+withdrawal,1,1,100.0";
+
+    let builder = PaymentEngine::builder()
+        .require_account_for_withdrawal(false)
+        .client(ClientConfig {
+            client: 1,
+            credit_limit: dec!(250),
+            ..ClientConfig::default()
+        });
+    let accounts = process_csv_with_builder(input, builder);
+
+    // The account is opened and the withdrawal drawn on the client's credit limit
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].available(), dec!(-100));
+}
+
+#[test]
+fn test_rejected_withdrawal_without_account_leaves_no_account() {
+    let input = "type,client,tx,amount
+deposit,1,1,10.0
+withdrawal,5,2,100.0";
+
+    let accounts = process_csv_with_builder(
+        input,
+        PaymentEngine::builder().require_account_for_withdrawal(false),
+    );
+
+    // The withdrawal fails for insufficient funds before an account is opened
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].client_id(), 1);
+}
+
+#[test]
+fn test_policy_chargeback_without_lock() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+chargeback,1,1,
+withdrawal,1,3,20.0";
+
+    let accounts =
+        process_csv_with_builder(input, PaymentEngine::builder().lock_on_chargeback(false));
+
+    assert_eq!(accounts[0].available(), dec!(30));
+    assert_eq!(accounts[0].total(), dec!(30));
+    assert!(!accounts[0].is_locked());
+}
+
+#[test]
+fn test_policy_allows_deposits_on_locked_account() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+chargeback,1,1,
+deposit,1,2,500.0";
+
+    let accounts = process_csv_with_builder(
+        input,
+        PaymentEngine::builder().reject_deposits_on_locked(false),
+    );
+
+    assert_eq!(accounts[0].total(), dec!(500));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_policy_loaded_from_toml() {
+    let policy = EnginePolicy::from_toml("lock_on_chargeback = false").unwrap();
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+chargeback,1,1,";
+
+    let accounts = process_csv_with_builder(input, PaymentEngine::builder().policy(policy));
+
+    assert_eq!(accounts[0].total(), dec!(0));
+    assert!(!accounts[0].is_locked());
+}
+
+// ============================================================================
+// Locked Accounts
+// ============================================================================
+
+#[test]
+fn test_open_dispute_on_locked_account_can_be_charged_back() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+dispute,1,2,
+chargeback,1,1,
+chargeback,1,2,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(0));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(0));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_open_dispute_on_locked_account_can_be_resolved() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+dispute,1,2,
+chargeback,1,1,
+resolve,1,2,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(50));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(50));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_locked_account_rejects_new_disputes_and_withdrawals() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+chargeback,1,1,
+dispute,1,2,
+withdrawal,1,3,10.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(50));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(50));
+}
+
+// ============================================================================
+// Administrative Transactions
+// ============================================================================
+
+#[test]
+fn test_unlock_after_chargeback_allows_transactions_again() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+chargeback,1,1,
+unlock,1,3,
+withdrawal,1,4,20.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(30));
+    assert!(!accounts[0].is_locked());
+}
+
+#[test]
+fn test_freeze_blocks_deposits_and_withdrawals() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+freeze,1,2,
+deposit,1,3,50.0
+withdrawal,1,4,20.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_freeze_unknown_account_is_ignored() {
+    let input = "type,client,tx,amount
+freeze,1,1,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts.len(), 0);
+}
+
+#[test]
+fn test_close_requires_zero_balance() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+close,1,2,
+withdrawal,1,3,30.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // Close is ignored because the account still holds funds
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert!(!accounts[0].is_locked());
+}
+
+#[test]
+fn test_closed_account_rejects_everything() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,100.0
+close,1,3,
+deposit,1,4,50.0
+unlock,1,5,";
+
+    let policy = EnginePolicy {
+        reject_deposits_on_locked: false,
+        ..EnginePolicy::default()
+    };
+    let output = process_csv_with_policy(input, policy);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].total(), dec!(0));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_admin_transaction_ids_are_unique() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+freeze,1,1,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert!(!accounts[0].is_locked());
+}
+
+#[test]
+fn test_rejects_admin_transaction_with_amount() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+freeze,1,2,10.0";
+
+    assert!(try_process_csv(input).is_err());
+}
+
+// ============================================================================
+// Account Status
+// ============================================================================
+
+#[test]
+fn test_default_export_has_basic_columns() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,100.0
+freeze,2,3,";
+
+    let mut engine = PaymentEngine::new();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    let mut output = Vec::new();
+    engine.export_accounts(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.starts_with("client,currency,available,held,total,locked\n"));
+    assert!(output.contains("1,USD,100.0000,0.0000,100.0000,false\n"));
+    assert!(output.contains("2,USD,100.0000,0.0000,100.0000,true\n"));
+}
+
+#[test]
+fn test_default_export_tells_currencies_of_a_client_apart() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,
+deposit,1,2,1500,JPY";
+
+    let output = try_process_csv(input).unwrap();
+
+    assert!(output.contains("1,USD,100.0000,0.0000,100.0000,false\n"));
+    assert!(output.contains("1,JPY,1500.0000,0.0000,1500.0000,false\n"));
+}
+
+#[test]
+fn test_status_column_is_exported() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,100.0
+freeze,2,3,";
+
+    let output = process_csv(input);
+
+    assert!(output.starts_with(
+        "client,currency,available,held,pending,authorized,reserved,total,headroom,locked,status\n"
+    ));
+    assert!(output
+        .contains("1,USD,100.0000,0.0000,0.0000,0.0000,0.0000,100.0000,100.0000,false,active\n"));
+    assert!(output.contains(
+        "2,USD,100.0000,0.0000,0.0000,0.0000,0.0000,100.0000,100.0000,true,frozen:compliance\n"
+    ));
+}
+
+#[test]
+fn test_chargeback_lock_and_compliance_freeze_are_lifted_independently() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+chargeback,1,1,
+freeze,1,3,
+unlock,1,4,
+withdrawal,1,5,20.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // The compliance freeze still blocks the withdrawal
+    assert_eq!(accounts[0].available(), dec!(50));
+    assert_eq!(
+        accounts[0].status(),
+        AccountStatus::Frozen(BTreeSet::from([FreezeReason::Compliance]))
+    );
+}
+
+#[test]
+fn test_unfreeze_keeps_chargeback_lock() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+chargeback,1,1,
+freeze,1,3,
+unfreeze,1,4,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(
+        accounts[0].status(),
+        AccountStatus::Frozen(BTreeSet::from([FreezeReason::Chargeback]))
+    );
+}
+
+#[test]
+fn test_unfreeze_lifts_compliance_freeze() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+freeze,1,2,
+unfreeze,1,3,
+withdrawal,1,4,30.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert_eq!(accounts[0].status(), AccountStatus::Active);
+}
+
+#[test]
+fn test_unfreeze_without_freeze_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+unfreeze,1,2,
+freeze,1,2,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // The failed unfreeze does not consume its transaction ID
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_withdraw_only_rejects_deposits() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdraw_only,1,2,
+deposit,1,3,50.0
+withdrawal,1,4,30.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert_eq!(accounts[0].status(), AccountStatus::WithdrawOnly);
+    assert!(!accounts[0].is_locked());
+}
+
+#[test]
+fn test_deposit_only_rejects_withdrawals() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit_only,1,2,
+withdrawal,1,3,30.0
+deposit,1,4,50.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(150));
+    assert_eq!(accounts[0].status(), AccountStatus::DepositOnly);
+}
+
+#[test]
+fn test_activate_lifts_restriction() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit_only,1,2,
+activate,1,3,
+withdrawal,1,4,30.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(70));
+    assert_eq!(accounts[0].status(), AccountStatus::Active);
+}
+
+#[test]
+fn test_freeze_takes_precedence_over_restriction() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdraw_only,1,2,
+freeze,1,3,
+withdrawal,1,4,30.0
+unfreeze,1,5,
+withdrawal,1,6,20.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // The restriction is back in force once the freeze is lifted
+    assert_eq!(accounts[0].available(), dec!(80));
+    assert_eq!(accounts[0].status(), AccountStatus::WithdrawOnly);
+}
+
+// ============================================================================
+// Transfers
+// ============================================================================
+
+fn account(accounts: &[Account], client: u16) -> &Account {
+    accounts.iter().find(|a| a.client_id() == client).unwrap()
+}
+
+#[test]
+fn test_transfer_moves_funds_between_clients() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+deposit,2,2,10.0,
+transfer,1,3,40.0,2";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(account(&accounts, 1).total(), dec!(60));
+    assert_eq!(account(&accounts, 2).total(), dec!(50));
+}
+
+#[test]
+fn test_transfer_creates_recipient_account() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+transfer,1,2,40.0,2";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(account(&accounts, 2).available(), dec!(40));
+}
+
+#[test]
+fn test_transfer_with_insufficient_funds_changes_nothing() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,30.0,
+transfer,1,2,40.0,2";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // Neither side is applied, the recipient account is not even created
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(account(&accounts, 1).available(), dec!(30));
+}
+
+#[test]
+fn test_transfer_to_locked_account_changes_nothing() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+deposit,2,2,10.0,
+freeze,2,3,,
+transfer,1,4,40.0,2";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(account(&accounts, 1).available(), dec!(100));
+    assert_eq!(account(&accounts, 2).available(), dec!(10));
+}
+
+#[test]
+fn test_transfer_from_locked_account_is_rejected() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+freeze,1,2,,
+transfer,1,3,40.0,2";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(account(&accounts, 1).available(), dec!(100));
+}
+
+#[test]
+fn test_transfer_dispute_holds_recipient_funds() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+transfer,1,2,40.0,2
+dispute,1,2,,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.transaction_state(2),
+        Some(TransactionState::Disputed)
+    );
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+    assert_eq!(account(&accounts, 1).total(), dec!(60));
+    assert_eq!(account(&accounts, 2).available(), dec!(0));
+    assert_eq!(account(&accounts, 2).held(), dec!(40));
+}
+
+#[test]
+fn test_transfer_chargeback_returns_funds_to_sender() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+transfer,1,2,40.0,2
+dispute,1,2,,
+chargeback,1,2,,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(account(&accounts, 1).available(), dec!(100));
+    assert!(!account(&accounts, 1).is_locked());
+    assert_eq!(account(&accounts, 2).total(), dec!(0));
+    assert!(!account(&accounts, 2).is_locked());
+}
+
+#[test]
+fn test_transfer_chargeback_moves_funds_between_clients() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+transfer,1,2,100.0,2
+withdrawal,2,3,60.0,
+dispute,1,2,,
+chargeback,1,2,,
+withdrawal,2,4,1.0,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    let ledger = engine.ledger();
+    let usd = Currency::default();
+
+    // Only the deposit and the recipient's withdrawal leave or enter the engine
+    assert_eq!(ledger.balance(usd, LedgerAccount::Chargebacks), dec!(0));
+    assert_eq!(ledger.balance(usd, LedgerAccount::Settlement), dec!(-40));
+    assert!(engine.check_trial_balance().is_ok());
+
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    let accounts = parse_output(&String::from_utf8(output).unwrap());
+    assert_eq!(account(&accounts, 1).available(), dec!(100));
+    // The recipient isn't locked, its overdrawn funds block the next withdrawal
+    assert_eq!(account(&accounts, 2).available(), dec!(-60));
+    assert_eq!(account(&accounts, 2).total(), dec!(-60));
+    assert!(!account(&accounts, 2).is_locked());
+}
+
+#[test]
+fn test_transfer_chargeback_to_closed_sender_is_rejected() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,40.0,
+transfer,1,2,40.0,2
+close,1,3,,
+dispute,1,2,,
+chargeback,1,2,,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.transaction_state(2),
+        Some(TransactionState::Disputed)
+    );
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // Neither side is applied, the funds stay held on the recipient
+    assert_eq!(account(&accounts, 1).total(), dec!(0));
+    assert_eq!(account(&accounts, 2).held(), dec!(40));
+    assert!(!account(&accounts, 2).is_locked());
+}
+
+#[test]
+fn test_transfer_dispute_by_recipient_is_rejected() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+transfer,1,2,40.0,2
+dispute,2,2,,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(account(&accounts, 2).available(), dec!(40));
+    assert_eq!(account(&accounts, 2).held(), dec!(0));
+}
+
+#[test]
+fn test_rejects_transfer_without_recipient() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+transfer,1,2,40.0,";
+
+    assert!(try_process_csv(input).is_err());
+}
+
+// ============================================================================
+// Refunds
+// ============================================================================
+
+#[test]
+fn test_partial_refunds_debit_the_account() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+refund,1,1,30.0
+refund,1,1,20.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(50));
+    assert_eq!(accounts[0].total(), dec!(50));
+}
+
+#[test]
+fn test_refunds_never_exceed_deposit() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,500.0
+refund,1,1,60.0
+refund,1,1,60.0
+refund,1,1,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Refunded)
+    );
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // The second refund exceeds the remaining 40, the last one refunds exactly that
+    assert_eq!(accounts[0].total(), dec!(500));
+}
+
+#[test]
+fn test_refunded_part_cannot_be_disputed() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+refund,1,1,70.0
+dispute,1,1,50.0
+dispute,1,1,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(engine.disputed_amount(1), Some(dec!(30)));
+}
+
+#[test]
+fn test_disputed_part_cannot_be_refunded() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,80.0
+refund,1,1,50.0
+refund,1,1,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // Only the undisputed 20 is refunded
+    assert_eq!(accounts[0].available(), dec!(0));
+    assert_eq!(accounts[0].held(), dec!(80));
+    assert_eq!(accounts[0].total(), dec!(80));
+}
+
+#[test]
+fn test_refund_of_other_clients_deposit_is_ignored() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,100.0
+refund,2,1,50.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert!(accounts.iter().all(|a| a.total() == dec!(100)));
+}
+
+#[test]
+fn test_refund_on_locked_account_is_rejected() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+freeze,1,2,
+refund,1,1,50.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+// ============================================================================
+// Adjustments
+// ============================================================================
+
+#[test]
+fn test_adjustments_credit_and_debit() {
+    let input = "type,client,tx,amount,to,reason
+deposit,1,1,100.0,,
+adjustment,1,2,15.5,,FEE_REVERSAL
+adjustment,1,3,-5.5,,DUPLICATE_CREDIT";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(110));
+    assert_eq!(accounts[0].total(), dec!(110));
+}
+
+#[test]
+fn test_adjustment_cannot_be_disputed() {
+    let input = "type,client,tx,amount,to,reason
+deposit,1,1,100.0,,
+adjustment,1,2,50.0,,GOODWILL
+dispute,1,2,,,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(engine.transaction_state(2), None);
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+    assert_eq!(accounts[0].held(), dec!(0));
+}
+
+#[test]
+fn test_adjustment_debit_requires_available_funds() {
+    let input = "type,client,tx,amount,to,reason
+deposit,1,1,10.0,,
+adjustment,1,2,-20.0,,CORRECTION";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].total(), dec!(10));
+}
+
+#[test]
+fn test_adjustment_on_restricted_account_depends_on_policy() {
+    let input = "type,client,tx,amount,to,reason
+deposit,1,1,100.0,,
+freeze,1,2,,,
+adjustment,1,3,-30.0,,CORRECTION";
+
+    let accounts = process_csv_with_builder(input, PaymentEngine::builder());
+    assert_eq!(accounts[0].total(), dec!(100));
+
+    let accounts = process_csv_with_builder(
+        input,
+        PaymentEngine::builder().allow_adjustments_on_restricted(true),
+    );
+    assert_eq!(accounts[0].total(), dec!(70));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_rejects_adjustment_without_reason() {
+    let input = "type,client,tx,amount,to,reason
+deposit,1,1,100.0,,
+adjustment,1,2,10.0,,";
+
+    assert!(try_process_csv(input).is_err());
+}
+
+// ============================================================================
+// Two-Phase Withdrawals
+// ============================================================================
+
+#[test]
+fn test_authorize_moves_funds_to_authorized() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+authorize,1,2,40.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(60));
+    assert_eq!(accounts[0].authorized(), dec!(40));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_capture_finalizes_authorization() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+authorize,1,2,40.0
+capture,1,2,
+void,1,2,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.authorization_state(2),
+        Some(AuthorizationState::Captured)
+    );
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    // The void after the capture is ignored
+    assert_eq!(accounts[0].available(), dec!(60));
+    assert_eq!(accounts[0].authorized(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(60));
+}
+
+#[test]
+fn test_void_releases_authorization() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+authorize,1,2,40.0
+void,1,2,
+capture,1,2,";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(
+        engine.authorization_state(2),
+        Some(AuthorizationState::Voided)
+    );
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].authorized(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_authorize_with_insufficient_funds_is_rejected() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+authorize,1,2,60.0
+authorize,1,3,60.0";
+
+    let engine = process_engine(input, EnginePolicy::default());
+    assert_eq!(engine.authorization_state(3), None);
+}
+
+#[test]
+fn test_authorized_funds_on_locked_account_can_only_be_voided() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+authorize,1,2,40.0
+freeze,1,3,
+capture,1,2,
+void,1,2,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_account_with_authorized_funds_cannot_be_closed() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+authorize,1,2,100.0
+close,1,3,
+capture,1,2,";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].total(), dec!(0));
+    assert_eq!(accounts[0].status(), AccountStatus::Active);
+}
+
+// ============================================================================
+// Uncleared Deposits
+// ============================================================================
+
+#[test]
+fn test_deposits_clear_after_configured_rows() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,50.0
+deposit,2,3,10.0
+withdrawal,1,4,50.0";
+
+    let accounts =
+        process_csv_with_builder(input, PaymentEngine::builder().deposit_clearing_rows(2));
+    let client = account(&accounts, 1);
+
+    // The first withdrawal is checked against uncleared funds only and fails
+    assert_eq!(client.available(), dec!(50));
+    assert_eq!(client.pending(), dec!(0));
+    assert_eq!(client.total(), dec!(50));
+
+    // Client 2's deposit is still waiting for one more row
+    assert_eq!(account(&accounts, 2).available(), dec!(0));
+    assert_eq!(account(&accounts, 2).pending(), dec!(10));
+}
+
+#[test]
+fn test_deposits_clear_immediately_by_default() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,50.0";
+
+    let output = process_csv(input);
+    let accounts = parse_output(&output);
+
+    assert_eq!(accounts[0].available(), dec!(50));
+    assert_eq!(accounts[0].pending(), dec!(0));
+}
+
+#[test]
+fn test_uncleared_deposit_can_be_disputed() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+resolve,1,1,";
+
+    let accounts =
+        process_csv_with_builder(input, PaymentEngine::builder().deposit_clearing_rows(5));
+
+    assert_eq!(accounts[0].available(), dec!(0));
+    assert_eq!(accounts[0].held(), dec!(0));
+    assert_eq!(accounts[0].pending(), dec!(100));
+    assert_eq!(accounts[0].total(), dec!(100));
+}
+
+#[test]
+fn test_uncleared_chargeback_nets_out_once_cleared() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+chargeback,1,1,";
+
+    let accounts =
+        process_csv_with_builder(input, PaymentEngine::builder().deposit_clearing_rows(2));
+
+    assert_eq!(accounts[0].available(), dec!(0));
+    assert_eq!(accounts[0].pending(), dec!(0));
+    assert_eq!(accounts[0].total(), dec!(0));
+    assert!(accounts[0].is_locked());
+}
+
+#[test]
+fn test_deposits_clear_after_configured_days() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+withdrawal,1,2,50.0,2024-01-02T23:59:59Z
+deposit,2,3,10.0,2024-01-03T00:00:00Z
+withdrawal,1,4,50.0,2024-01-03T00:00:01Z";
+
+    let accounts =
+        process_csv_with_builder(input, PaymentEngine::builder().deposit_clearing_days(2));
+
+    // The first withdrawal comes a second too early and fails
+    assert_eq!(account(&accounts, 1).available(), dec!(50));
+    assert_eq!(account(&accounts, 1).pending(), dec!(0));
+    assert_eq!(account(&accounts, 2).pending(), dec!(10));
+}
+
+#[test]
+fn test_deposits_clear_after_days_or_rows_whichever_ends_first() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+deposit,2,2,10.0,2024-01-05T00:00:00Z
+deposit,3,3,10.0,2024-01-05T00:00:01Z
+deposit,3,4,10.0,";
+
+    let builder = PaymentEngine::builder()
+        .deposit_clearing_rows(2)
+        .deposit_clearing_days(1);
+    let accounts = process_csv_with_builder(input, builder);
+
+    // Client 1 cleared by time on the next row, client 2 by row count
+    assert_eq!(account(&accounts, 1).available(), dec!(100));
+    assert_eq!(account(&accounts, 2).available(), dec!(10));
+    // A row without a timestamp takes the time of the latest row
+    assert_eq!(account(&accounts, 3).pending(), dec!(20));
+}
+
+// ============================================================================
+// Rolling Reserves
+// ============================================================================
+
+fn reserve_builder(reserve_rows: u64) -> PaymentEngineBuilder {
+    PaymentEngine::builder().client(ClientConfig {
+        client: 1,
+        reserve_percentage: dec!(10),
+        reserve_rows,
+        ..ClientConfig::default()
+    })
+}
+
+#[test]
+fn test_reserve_is_withheld_from_available() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,95.0
+deposit,2,3,100.0";
+
+    let accounts = process_csv_with_builder(input, reserve_builder(10));
+    let client = account(&accounts, 1);
+
+    // The withdrawal exceeds the unreserved 90 and fails
+    assert_eq!(client.available(), dec!(90));
+    assert_eq!(client.reserved(), dec!(10));
+    assert_eq!(client.total(), dec!(100));
+
+    // Unconfigured clients keep the whole deposit available
+    assert_eq!(account(&accounts, 2).available(), dec!(100));
+    assert_eq!(account(&accounts, 2).reserved(), dec!(0));
+}
+
+#[test]
+fn test_reserve_is_released_after_configured_rows() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,1.0
+withdrawal,1,3,95.0";
+
+    let accounts = process_csv_with_builder(input, reserve_builder(1));
+    let client = account(&accounts, 1);
+
+    assert_eq!(client.available(), dec!(5));
+    assert_eq!(client.reserved(), dec!(0));
+    assert_eq!(client.total(), dec!(5));
+}
+
+#[test]
+fn test_reserve_is_released_after_configured_days() {
+    let builder = || {
+        PaymentEngine::builder().client(ClientConfig {
+            client: 1,
+            reserve_percentage: dec!(10),
+            reserve_days: 30,
+            ..ClientConfig::default()
+        })
+    };
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+deposit,2,2,1.0,2024-01-30T00:00:00Z";
+
+    let accounts = process_csv_with_builder(input, builder());
+    assert_eq!(account(&accounts, 1).reserved(), dec!(10));
+
+    let input = format!("{input}\ndeposit,2,3,1.0,2024-01-31T00:00:00Z");
+    let accounts = process_csv_with_builder(&input, builder());
+    assert_eq!(account(&accounts, 1).available(), dec!(100));
+    assert_eq!(account(&accounts, 1).reserved(), dec!(0));
+}
+
+#[test]
+fn test_reserve_with_clearing_period() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,1.0";
+
+    let builder = reserve_builder(5).deposit_clearing_rows(1);
+    let accounts = process_csv_with_builder(input, builder);
+    let client = account(&accounts, 1);
+
+    // The unreserved part cleared, the reserve is still held back
+    assert_eq!(client.available(), dec!(90));
+    assert_eq!(client.pending(), dec!(0));
+    assert_eq!(client.reserved(), dec!(10));
+    assert_eq!(client.total(), dec!(100));
+}
+
+#[test]
+fn test_reserved_column_is_exported() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0";
+
+    let mut engine = reserve_builder(10).build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(
+        output.contains("1,USD,90.0000,0.0000,0.0000,0.0000,10.0000,100.0000,90.0000,false,active")
+    );
+}
+
+// ============================================================================
+// Credit Limits
+// ============================================================================
+
+fn credit_builder(credit_limit: Decimal) -> PaymentEngineBuilder {
+    PaymentEngine::builder().client(ClientConfig {
+        client: 1,
+        credit_limit,
+        ..ClientConfig::default()
+    })
+}
+
+#[test]
+fn test_withdrawal_within_credit_limit() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,150.0
+withdrawal,1,3,60.0";
+
+    let accounts = process_csv_with_builder(input, credit_builder(dec!(100)));
+
+    // The second withdrawal would exceed the limit and fails
+    assert_eq!(accounts[0].available(), dec!(-50));
+    assert_eq!(accounts[0].total(), dec!(-50));
+    assert_eq!(accounts[0].credit_limit(), dec!(100));
+    assert_eq!(accounts[0].headroom(), dec!(50));
+}
+
+#[test]
+fn test_no_credit_limit_by_default() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,150.0";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(accounts[0].available(), dec!(100));
+    assert_eq!(accounts[0].headroom(), dec!(100));
+}
+
+#[test]
+fn test_credit_limit_transaction_sets_limit() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+credit_limit,1,2,50.0
+withdrawal,1,3,140.0
+credit_limit,1,4,0
+withdrawal,1,5,1.0";
+
+    let accounts = parse_output(&process_csv(input));
+
+    // Removing the limit leaves a negative headroom and blocks further debits
+    assert_eq!(accounts[0].available(), dec!(-40));
+    assert_eq!(accounts[0].credit_limit(), dec!(0));
+    assert_eq!(accounts[0].headroom(), dec!(-40));
+}
+
+#[test]
+fn test_credit_limit_replaces_configured_limit() {
+    let input = "type,client,tx,amount
+deposit,1,1,10.0
+credit_limit,1,2,20.0
+withdrawal,1,3,40.0";
+
+    let accounts = process_csv_with_builder(input, credit_builder(dec!(100)));
+
+    assert_eq!(accounts[0].available(), dec!(10));
+    assert_eq!(accounts[0].headroom(), dec!(30));
+}
+
+#[test]
+fn test_headroom_column_is_exported() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,130.0";
+
+    let mut engine = credit_builder(dec!(50)).build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(
+        output.contains("1,USD,-30.0000,0.0000,0.0000,0.0000,0.0000,-30.0000,20.0000,false,active")
+    );
+}
+
+// ============================================================================
+// Fees
+// ============================================================================
+
+const HOUSE: u16 = 99;
+
+fn fee_builder() -> PaymentEngineBuilder {
+    PaymentEngine::builder().fees(FeeSchedule {
+        house_account: Some(HOUSE),
+        deposit: Some(Fee {
+            percentage: dec!(1),
+            ..Fee::default()
+        }),
+        withdrawal: Some(Fee {
+            flat: dec!(2),
+            max: Some(dec!(5)),
+            tiers: vec![FeeTier {
+                from: dec!(100),
+                flat: dec!(1),
+                percentage: dec!(10),
+            }],
+            ..Fee::default()
+        }),
+    })
+}
+
+#[test]
+fn test_fees_are_credited_to_house_account() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,50.0
+withdrawal,1,3,40.0";
+
+    let accounts = process_csv_with_builder(input, fee_builder());
+
+    // Deposit fee 1, withdrawal fees 2 each
+    assert_eq!(account(&accounts, 1).available(), dec!(5));
+    assert_eq!(account(&accounts, HOUSE).available(), dec!(5));
+    assert_eq!(account(&accounts, HOUSE).total(), dec!(5));
+}
+
+#[test]
+fn test_withdrawal_fee_must_be_covered() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,98.0";
+
+    let accounts = process_csv_with_builder(input, fee_builder());
+
+    // 99 available can't cover 98 plus the fee of 2
+    assert_eq!(account(&accounts, 1).available(), dec!(99));
+    assert_eq!(account(&accounts, HOUSE).available(), dec!(1));
+}
+
+#[test]
+fn test_tiered_fee_is_capped() {
+    let input = "type,client,tx,amount
+deposit,1,1,1000.0
+withdrawal,1,2,200.0";
+
+    let accounts = process_csv_with_builder(input, fee_builder());
+
+    // Tier fee of 21 is capped at 5
+    assert_eq!(account(&accounts, 1).available(), dec!(785));
+    assert_eq!(account(&accounts, HOUSE).available(), dec!(15));
+}
+
+#[test]
+fn test_fee_is_rounded_to_4_places() {
+    let input = "type,client,tx,amount
+deposit,1,1,0.1234";
+
+    let accounts = process_csv_with_builder(input, fee_builder());
+
+    assert_eq!(account(&accounts, 1).available(), dec!(0.1222));
+    assert_eq!(account(&accounts, HOUSE).available(), dec!(0.0012));
+}
+
+#[test]
+fn test_deposit_fee_taking_the_whole_amount_is_rejected() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,2.0
+deposit,2,3,1.5";
+
+    let builder = PaymentEngine::builder().fees(FeeSchedule {
+        house_account: Some(HOUSE),
+        deposit: Some(Fee {
+            flat: dec!(2),
+            ..Fee::default()
+        }),
+        withdrawal: None,
+    });
+    let engine = process_with_engine(input, builder);
+
+    // Neither deposit is kept, client 2 doesn't even get an account
+    assert!(engine.get_transaction(2).is_none());
+    assert!(engine.get_transaction(3).is_none());
+    assert_eq!(engine.client_accounts(2).count(), 0);
+    assert_eq!(engine.get_account(1, usd()).unwrap().available(), dec!(98));
+    assert_eq!(
+        engine.get_account(HOUSE, usd()).unwrap().available(),
+        dec!(2)
+    );
+}
+
+#[test]
+fn test_rejected_transactions_are_not_charged() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+freeze,1,2,
+withdrawal,1,3,10.0";
+
+    let accounts = process_csv_with_builder(input, fee_builder());
+
+    assert_eq!(account(&accounts, 1).available(), dec!(99));
+    assert_eq!(account(&accounts, HOUSE).available(), dec!(1));
+}
+
+#[test]
+fn test_dispute_refers_to_net_deposit() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+chargeback,1,1,";
+
+    let accounts = process_csv_with_builder(input, fee_builder());
+
+    assert_eq!(account(&accounts, 1).total(), dec!(0));
+    assert_eq!(account(&accounts, HOUSE).total(), dec!(1));
+}
+
+// ============================================================================
+// Ledger
+// ============================================================================
+
+#[test]
+fn test_ledger_mirrors_every_balance_change() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,2,2,50.0
+withdrawal,1,3,20.0
+dispute,2,2,
+chargeback,2,2,
+withdrawal,1,4,500.0";
+
+    let mut engine = PaymentEngine::new();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    let ledger = engine.ledger();
+
+    // Rejected transactions post nothing
+    assert_eq!(ledger.entry_count(), 5);
+    let usd = Currency::default();
+    assert_eq!(ledger.trial_balance(usd), dec!(0));
+    assert_eq!(ledger.balance(usd, LedgerAccount::Settlement), dec!(-130));
+    assert_eq!(ledger.balance(usd, LedgerAccount::Chargebacks), dec!(50));
+    assert!(engine.check_trial_balance().is_ok());
+}
+
+#[test]
+fn test_ledger_stays_balanced_across_features() {
+    let input = "type,client,tx,amount,to,reason
+deposit,1,1,100.0,,
+transfer,1,2,30.0,2,
+authorize,2,3,10.0,,
+capture,2,3,,,
+adjustment,1,4,-5.0,,FEE
+refund,1,1,20.0,,";
+
+    let mut engine = fee_builder().deposit_clearing_rows(2).build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+
+    assert!(engine.ledger().is_balanced());
+    assert!(engine.check_trial_balance().is_ok());
+}
+
+// ============================================================================
+// Multi-Currency
+// ============================================================================
+
+fn currency_account<'a>(accounts: &'a [Account], client: u16, currency: &str) -> &'a Account {
+    let currency: Currency = currency.parse().unwrap();
+    accounts
+        .iter()
+        .find(|a| a.client_id() == client && a.currency() == currency)
+        .unwrap()
+}
+
+#[test]
+fn test_balances_are_kept_per_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,
+deposit,1,2,1500,JPY
+withdrawal,1,3,500,jpy
+deposit,2,4,0.5,BTC";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(accounts.len(), 3);
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(100));
+    assert_eq!(
+        currency_account(&accounts, 1, "JPY").available(),
+        dec!(1000)
+    );
+    assert_eq!(currency_account(&accounts, 2, "BTC").total(), dec!(0.5));
+}
+
+#[test]
+fn test_export_has_one_row_per_client_and_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,1500,JPY
+deposit,1,2,0.00000001,BTC";
+
+    let output = process_csv(input);
+
+    assert!(output.starts_with("client,currency,available,"));
+    assert!(output.contains("1,JPY,1500.0000,"));
+    assert!(output.contains("1,BTC,0.00000001,"));
+}
+
+#[test]
+fn test_amounts_must_fit_the_currency_scale() {
+    for input in [
+        "type,client,tx,amount,currency\ndeposit,1,1,1.5,JPY",
+        "type,client,tx,amount,currency\ndeposit,1,1,0.000000001,BTC",
+    ] {
+        assert!(try_process_csv(input).is_err(), "{input}");
+    }
+    let input = "type,client,tx,amount,currency\ndeposit,1,1,0.12345678,BTC";
+    assert!(try_process_csv(input).is_ok());
+}
+
+#[test]
+fn test_withdrawal_draws_only_on_its_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,USD
+withdrawal,1,2,50,EUR
+deposit,1,3,20,EUR
+withdrawal,1,4,50,EUR";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(100));
+    assert_eq!(currency_account(&accounts, 1, "EUR").available(), dec!(20));
+}
+
+#[test]
+fn test_dispute_must_be_in_transaction_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,1500,JPY
+dispute,1,1,,USD
+dispute,1,1,500,JPY";
+
+    let accounts = parse_output(&process_csv(input));
+
+    let jpy = currency_account(&accounts, 1, "JPY");
+    assert_eq!(jpy.available(), dec!(1000));
+    assert_eq!(jpy.held(), dec!(500));
+}
+
+#[test]
+fn test_rows_without_currency_take_the_referenced_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100,EUR
+dispute,1,1,40,
+resolve,1,1,,
+refund,1,1,30,
+dispute,1,1,,
+chargeback,1,1,,";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(accounts.len(), 1);
+    let eur = currency_account(&accounts, 1, "EUR");
+    assert_eq!(eur.total(), dec!(0));
+    assert!(eur.is_locked());
+}
+
+#[test]
+fn test_partial_amount_must_fit_the_referenced_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,1500,JPY
+dispute,1,1,0.5,";
+
+    let report = report_for(input, PaymentEngine::builder());
+
+    assert_eq!(report.errors_of_kind("amount_too_precise"), 1);
+}
+
+#[test]
+fn test_chargeback_locks_every_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,
+deposit,1,2,1500,JPY
+dispute,1,2,,JPY
+chargeback,1,2,,JPY
+deposit,1,3,10,
+deposit,1,4,10,EUR";
+
+    let accounts = parse_output(&process_csv(input));
+
+    // The rejected EUR deposit opens no account
+    assert_eq!(accounts.len(), 2);
+    assert!(currency_account(&accounts, 1, "USD").is_locked());
+    assert_eq!(currency_account(&accounts, 1, "USD").total(), dec!(100));
+    assert_eq!(currency_account(&accounts, 1, "JPY").total(), dec!(0));
+}
+
+#[test]
+fn test_admin_rows_ignore_the_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,10.0,
+deposit,1,2,5,EUR
+freeze,1,3,,EUR
+withdrawal,1,4,5.0,";
+
+    let accounts = parse_output(&process_csv(input));
+
+    // The freeze applies to USD too
+    assert!(currency_account(&accounts, 1, "USD").is_locked());
+    assert_eq!(currency_account(&accounts, 1, "USD").total(), dec!(10));
+}
+
+#[test]
+fn test_close_requires_every_currency_to_be_empty() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,10.0,
+deposit,1,2,5,EUR
+withdrawal,1,3,10.0,
+close,1,4,,
+withdrawal,1,5,5,EUR
+close,1,6,,";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(accounts.len(), 2);
+    assert!(accounts.iter().all(Account::is_closed));
+}
+
+#[test]
+fn test_transfer_stays_in_its_currency() {
+    let input = "type,client,tx,amount,to,currency
+deposit,1,1,1500,,JPY
+transfer,1,2,500,2,JPY
+transfer,1,3,500,2,";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(
+        currency_account(&accounts, 1, "JPY").available(),
+        dec!(1000)
+    );
+    assert_eq!(currency_account(&accounts, 2, "JPY").available(), dec!(500));
+}
+
+#[test]
+fn test_fees_apply_to_every_currency_and_policy_credit_limits_to_default_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,
+deposit,1,2,100.0,EUR
+withdrawal,1,3,150,EUR";
+
+    let builder = fee_builder().client(ClientConfig {
+        client: 1,
+        credit_limit: dec!(100),
+        ..ClientConfig::default()
+    });
+    let accounts = process_csv_with_builder(input, builder);
+
+    // The EUR withdrawal can't draw on the USD credit limit
+    let eur = currency_account(&accounts, 1, "EUR");
+    assert_eq!(eur.available(), dec!(99));
+    assert_eq!(eur.credit_limit(), dec!(0));
+    assert_eq!(
+        currency_account(&accounts, HOUSE, "EUR").available(),
+        dec!(1)
+    );
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(99));
+    assert_eq!(
+        currency_account(&accounts, 1, "USD").credit_limit(),
+        dec!(100)
+    );
+}
+
+#[test]
+fn test_ledger_balances_each_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,
+deposit,1,2,1500,JPY
+withdrawal,1,3,500,JPY";
+
+    let mut engine = PaymentEngine::new();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    let ledger = engine.ledger();
+
+    let jpy: Currency = "JPY".parse().unwrap();
+    assert_eq!(ledger.trial_balances().len(), 2);
+    assert_eq!(ledger.balance(jpy, LedgerAccount::Settlement), dec!(-1000));
+    assert!(engine.check_trial_balance().is_ok());
+}
+
+// ============================================================================
+// FX Conversion
+// ============================================================================
+
+fn fx_builder() -> PaymentEngineBuilder {
+    let rates = "base,quote,rate,spread,from_row
+USD,EUR,0.92,,
+USD,EUR,0.95,,4
+USD,JPY,151.37,1,
+EUR,USD,1.08,0.5,";
+    PaymentEngine::builder().rates(RateTable::from_csv(rates.as_bytes()).unwrap())
+}
+
+#[test]
+fn test_convert_moves_funds_between_currencies() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,100.0,,
+convert,1,2,40,USD,EUR";
+
+    let accounts = process_csv_with_builder(input, fx_builder());
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(60));
+    assert_eq!(
+        currency_account(&accounts, 1, "EUR").available(),
+        dec!(36.8)
+    );
+}
+
+#[test]
+fn test_convert_uses_rate_in_effect_at_row() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,100.0,,
+convert,1,2,10,,EUR
+deposit,2,3,1,,
+convert,1,4,10,,EUR";
+
+    let mut engine = fx_builder().build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+
+    assert_eq!(engine.conversion(2).unwrap().converted(), dec!(9.2));
+    assert_eq!(engine.conversion(4).unwrap().converted(), dec!(9.5));
+    assert_eq!(engine.conversion(4).unwrap().row(), 4);
+    assert_eq!(engine.conversion(4).unwrap().rate().rate, dec!(0.95));
+}
+
+#[test]
+fn test_convert_uses_rate_in_effect_at_timestamp() {
+    let rates = "base,quote,rate,spread,from,from_row
+USD,EUR,0.92,,,
+USD,EUR,0.95,,2024-06-01T00:00:00Z,";
+    let builder = PaymentEngine::builder().rates(RateTable::from_csv(rates.as_bytes()).unwrap());
+    let input = "type,client,tx,amount,currency,to_currency,timestamp
+deposit,1,1,100.0,,,
+convert,1,2,10,,EUR,2024-06-02T00:00:00Z
+convert,1,3,10,,EUR,2024-05-31T00:00:00Z
+convert,1,4,10,,EUR,";
+
+    let mut engine = builder.build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+
+    assert_eq!(engine.conversion(2).unwrap().converted(), dec!(9.5));
+    assert_eq!(engine.conversion(3).unwrap().converted(), dec!(9.2));
+    assert_eq!(engine.conversion(4).unwrap().converted(), dec!(9.2));
+}
+
+#[test]
+fn test_convert_deducts_spread_and_rounds_down_to_target_scale() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,100.0,,
+convert,1,2,10,,JPY
+convert,1,3,0.0001,,JPY";
+
+    let accounts = process_csv_with_builder(input, fx_builder());
+
+    // 10 * 151.37 = 1513.7, minus the 1% spread = 1498.563
+    assert_eq!(
+        currency_account(&accounts, 1, "JPY").available(),
+        dec!(1498)
+    );
+    // 0.0001 USD is worth less than 1 JPY, so the conversion is rejected
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(90));
+}
+
+#[test]
+fn test_convert_without_rate_is_rejected() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,1500,JPY,
+convert,1,2,1000,JPY,USD";
+
+    let accounts = process_csv_with_builder(input, fx_builder());
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(
+        currency_account(&accounts, 1, "JPY").available(),
+        dec!(1500)
+    );
+}
+
+#[test]
+fn test_convert_follows_withdrawal_rules() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,10.0,,
+convert,1,2,20,,EUR
+convert,2,3,1,,EUR
+deposit,3,4,10.0,,
+freeze,3,5,,,
+convert,3,6,5,,EUR";
+
+    let accounts = process_csv_with_builder(input, fx_builder());
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(10));
+    assert_eq!(currency_account(&accounts, 3, "USD").available(), dec!(10));
+}
+
+#[test]
+fn test_convert_rejects_invalid_rows() {
+    for input in [
+        "type,client,tx,amount,currency,to_currency\nconvert,1,1,10,USD,",
+        "type,client,tx,amount,currency,to_currency\nconvert,1,1,10,USD,USD",
+        "type,client,tx,amount,currency,to_currency\nconvert,1,1,1.5,JPY,USD",
+    ] {
+        assert!(try_process_csv(input).is_err(), "{input}");
+    }
+}
+
+#[test]
+fn test_convert_replay_is_not_converted_twice() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,100.0,,
+convert,1,2,40,,EUR
+convert,1,2,40,,EUR";
+
+    let accounts = process_csv_with_builder(input, fx_builder().idempotent_replays(true));
+
+    assert_eq!(currency_account(&accounts, 1, "USD").available(), dec!(60));
+    assert_eq!(
+        currency_account(&accounts, 1, "EUR").available(),
+        dec!(36.8)
+    );
+}
+
+#[test]
+fn test_ledger_books_conversions_per_currency() {
+    let input = "type,client,tx,amount,currency,to_currency
+deposit,1,1,100.0,,
+convert,1,2,40,,EUR
+convert,1,3,10,EUR,USD";
+
+    let mut engine = fx_builder().build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    let ledger = engine.ledger();
+
+    let (usd, eur) = (Currency::default(), "EUR".parse().unwrap());
+    // 10 EUR * 1.08 = 10.8 USD, minus the 0.5% spread = 10.746 USD
+    assert_eq!(ledger.balance(usd, LedgerAccount::Exchange), dec!(29.254));
+    assert_eq!(ledger.balance(eur, LedgerAccount::Exchange), dec!(-26.8));
+    assert!(engine.check_trial_balance().is_ok());
+}
+
+// ============================================================================
+// Dispute Expiry
+// ============================================================================
+
+fn process_with_engine(input: &str, builder: PaymentEngineBuilder) -> PaymentEngine {
+    let mut engine = builder.build();
+    engine.process_transactions(Cursor::new(input)).unwrap();
+    engine
+}
+
+#[test]
+fn test_disputes_stay_open_by_default() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+deposit,1,2,1.0
+deposit,1,3,1.0
+deposit,1,4,1.0";
+
+    let engine = process_with_engine(input, PaymentEngine::builder());
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+    assert!(engine.expired_disputes().is_empty());
+}
+
+#[test]
+fn test_expired_dispute_is_resolved() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+deposit,1,2,1.0
+deposit,1,3,1.0";
+
+    let builder = PaymentEngine::builder().dispute_expiry_rows(2);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+    let expired = engine.expired_disputes();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].transaction_id(), 1);
+    assert_eq!(expired[0].client_id(), 1);
+    assert_eq!(expired[0].amount(), dec!(100));
+    assert_eq!(expired[0].action(), ExpiryAction::Resolve);
+    assert_eq!(expired[0].row(), 4);
+}
+
+#[test]
+fn test_dispute_does_not_expire_before_window_ends() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+deposit,1,2,1.0";
+
+    let builder = PaymentEngine::builder().dispute_expiry_rows(2);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+    assert!(engine.expired_disputes().is_empty());
+}
+
+#[test]
+fn test_expired_dispute_is_charged_back() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+dispute,1,1,
+deposit,2,3,1.0";
+
+    let builder = PaymentEngine::builder()
+        .dispute_expiry_rows(1)
+        .dispute_expiry_action(ExpiryAction::Chargeback);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::ChargedBack)
+    );
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    let accounts = parse_output(&String::from_utf8(output).unwrap());
+    let account = currency_account(&accounts, 1, "USD");
+    assert_eq!(account.total(), dec!(50));
+    assert_eq!(account.held(), dec!(0));
+    // Expired chargebacks lock the account like regular chargebacks
+    assert!(account.is_locked());
+}
+
+#[test]
+fn test_settled_dispute_does_not_expire() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+resolve,1,1,
+deposit,1,2,1.0
+deposit,1,3,1.0";
+
+    let builder = PaymentEngine::builder()
+        .dispute_expiry_rows(2)
+        .dispute_expiry_action(ExpiryAction::Chargeback);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+    assert!(engine.expired_disputes().is_empty());
+}
+
+#[test]
+fn test_redispute_starts_a_new_window() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+resolve,1,1,
+dispute,1,1,
+deposit,1,2,1.0
+deposit,1,3,1.0";
+
+    // The first dispute's window ends after row 5, the second one's after row 7
+    let builder = PaymentEngine::builder().dispute_expiry_rows(3);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+    assert!(engine.expired_disputes().is_empty());
+}
+
+#[test]
+fn test_partial_disputes_expire_with_the_first_window() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,30
+dispute,1,1,20
+deposit,1,2,1.0";
+
+    let builder = PaymentEngine::builder().dispute_expiry_rows(2);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+    assert_eq!(engine.expired_disputes()[0].amount(), dec!(50));
+    assert!(engine.check_trial_balance().is_ok());
+}
+
+#[test]
+fn test_expiry_is_loaded_from_policy() {
+    let policy =
+        EnginePolicy::from_toml("dispute_expiry_rows = 1\ndispute_expiry_action = \"chargeback\"")
+            .unwrap();
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,
+deposit,2,2,1.0";
+
+    let engine = process_with_engine(input, PaymentEngine::builder().policy(policy));
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::ChargedBack)
+    );
+}
+
+// ============================================================================
+// Timestamps
+// ============================================================================
+
+#[test]
+fn test_timestamp_column_accepts_rfc3339_and_epoch_millis() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-03-01T12:00:00Z
+deposit,1,2,100.0,1709294400000
+deposit,1,3,100.0,";
+
+    let accounts = parse_output(&process_csv(input));
+
+    assert_eq!(accounts[0].available(), dec!(300));
+}
+
+#[test]
+fn test_invalid_timestamp_is_rejected() {
+    let input = "type,client,tx,amount,timestamp\ndeposit,1,1,100.0,yesterday";
+    assert!(try_process_csv(input).is_err());
+}
+
+#[test]
+fn test_dispute_within_window_is_accepted() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+dispute,1,1,,2024-04-30T00:00:00Z";
+
+    let builder = PaymentEngine::builder().dispute_window_days(120);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+}
+
+#[test]
+fn test_dispute_after_window_is_rejected() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+dispute,1,1,,2024-05-01T00:00:01Z";
+
+    let builder = PaymentEngine::builder().dispute_window_days(120);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+}
+
+#[test]
+fn test_dispute_without_timestamp_uses_latest_timestamp() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+deposit,2,2,100.0,2024-06-01T00:00:00Z
+dispute,1,1,,";
+
+    let builder = PaymentEngine::builder().dispute_window_days(120);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+}
+
+#[test]
+fn test_transactions_without_timestamp_can_always_be_disputed() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,
+deposit,2,2,100.0,2030-01-01T00:00:00Z
+dispute,1,1,,";
+
+    let builder = PaymentEngine::builder().dispute_window_days(1);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+}
+
+#[test]
+fn test_dispute_expires_after_days() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+dispute,1,1,,2024-01-02T00:00:00Z
+deposit,2,2,1.0,2024-01-31T23:59:59Z
+deposit,2,3,1.0,2024-02-01T00:00:00Z";
+
+    let builder = PaymentEngine::builder()
+        .dispute_expiry_days(30)
+        .dispute_expiry_action(ExpiryAction::Chargeback);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::ChargedBack)
+    );
+    let expired = &engine.expired_disputes()[0];
+    assert_eq!(expired.row(), 4);
+    assert_eq!(
+        expired.timestamp().map(|t| t.to_string()),
+        Some("2024-02-01T00:00:00Z".to_string())
+    );
+}
+
+#[test]
+fn test_dispute_expires_by_rows_or_days_whichever_first() {
+    let input = "type,client,tx,amount,timestamp
+deposit,1,1,100.0,2024-01-01T00:00:00Z
+dispute,1,1,,2024-01-01T00:00:00Z
+deposit,2,2,1.0,2024-01-01T00:00:00Z";
+
+    let builder = PaymentEngine::builder()
+        .dispute_expiry_rows(1)
+        .dispute_expiry_days(30);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+    assert_eq!(engine.expired_disputes().len(), 1);
+}
+
+// ============================================================================
+// Reorder Buffer
+// ============================================================================
+
+#[test]
+fn test_early_dispute_is_rejected_by_default() {
+    let input = "type,client,tx,amount
+dispute,1,1,
+deposit,1,1,100.0";
+
+    let engine = process_with_engine(input, PaymentEngine::builder());
+
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+    assert!(engine.unmatched_rows().is_empty());
+}
+
+#[test]
+fn test_early_dispute_is_applied_when_deposit_arrives() {
+    let input = "type,client,tx,amount
+dispute,1,1,
+deposit,2,2,50.0
+deposit,1,1,100.0";
+
+    let builder = PaymentEngine::builder().reorder_window_rows(5);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+    assert!(engine.unmatched_rows().is_empty());
+
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    let accounts = parse_output(&String::from_utf8(output).unwrap());
+    assert_eq!(currency_account(&accounts, 1, "USD").held(), dec!(100));
+}
+
+#[test]
+fn test_parked_rows_are_retried_in_row_order() {
+    let input = "type,client,tx,amount
+dispute,1,1,
+chargeback,1,1,
+deposit,1,1,100.0";
+
+    let builder = PaymentEngine::builder().reorder_window_rows(5);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::ChargedBack)
+    );
+}
+
+#[test]
+fn test_parked_row_expires_after_window() {
+    let input = "type,client,tx,amount
+dispute,1,1,
+deposit,2,2,1.0
+deposit,2,3,1.0
+deposit,1,1,100.0";
+
+    let builder = PaymentEngine::builder().reorder_window_rows(2);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+    let unmatched = engine.unmatched_rows();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].row(), 1);
+    assert_eq!(unmatched[0].referenced_tx_id(), 1);
+}
+
+#[test]
+fn test_full_reorder_buffer_drops_oldest_row() {
+    let input = "type,client,tx,amount
+dispute,1,1,
+dispute,1,2,
+deposit,1,1,100.0
+deposit,1,2,100.0";
+
+    let builder = PaymentEngine::builder()
+        .reorder_window_rows(10)
+        .reorder_buffer_size(1);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+    assert_eq!(
+        engine.transaction_state(2),
+        Some(TransactionState::Disputed)
+    );
+    assert_eq!(engine.unmatched_rows()[0].row(), 1);
+}
+
+#[test]
+fn test_rows_still_parked_at_end_of_input_are_unmatched() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+refund,1,7,
+dispute,1,1,";
+
+    let builder = PaymentEngine::builder().reorder_window_rows(10);
+    let engine = process_with_engine(input, builder);
+
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Disputed)
+    );
+    let unmatched = engine.unmatched_rows();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].row(), 2);
+    assert_eq!(unmatched[0].referenced_tx_id(), 7);
+}
+
+#[test]
+fn test_parked_row_failing_on_retry_is_skipped() {
+    let input = "type,client,tx,amount
+dispute,2,1,
+deposit,1,1,100.0";
+
+    let builder = PaymentEngine::builder().reorder_window_rows(5);
+    let engine = process_with_engine(input, builder);
+
+    // The dispute comes from another client, it matches the deposit but is rejected
+    assert_eq!(engine.transaction_state(1), Some(TransactionState::Applied));
+    assert!(engine.unmatched_rows().is_empty());
+}
+
+#[test]
+fn test_row_referencing_ineligible_transaction_is_not_parked() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,40.0
+dispute,1,2,
+refund,1,2,";
+    let builder = || PaymentEngine::builder().reorder_window_rows(5);
+
+    // Withdrawal disputes are disallowed by default and only deposits can be refunded
+    let engine = process_with_engine(input, builder());
+    assert_eq!(engine.transaction_state(2), Some(TransactionState::Applied));
+    assert!(engine.unmatched_rows().is_empty());
+
+    let report = report_for(input, builder());
+    assert_eq!(report.rejected(), 2);
+    for (rejected, row) in report.rejected_rows().iter().zip([3, 4]) {
+        assert_eq!(rejected.row(), row);
+        assert_eq!(rejected.kind(), "ineligible_transaction");
+    }
+}
+
+// ============================================================================
+// Typed Transactions
+// ============================================================================
+
+fn usd() -> Currency {
+    "USD".parse().unwrap()
+}
+
+fn export(engine: &PaymentEngine) -> Vec<Account> {
+    let mut output = Vec::new();
+    engine
+        .export_accounts_as(&mut output, ExportFormat::Extended)
+        .unwrap();
+    parse_output(&String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_apply_typed_transactions() {
+    let mut engine = PaymentEngine::new();
+
+    let deposit = Deposit::new(1, 1, dec!(100), usd(), None).unwrap();
+    let withdrawal = Withdrawal::new(1, 2, dec!(30), usd(), None).unwrap();
+    let dispute = Dispute::new(1, 1, Some(dec!(20)), Some(usd()), None).unwrap();
+    let resolve = Resolve::new(1, 1, None, Some(usd()), None).unwrap();
+    for transaction in [
+        Transaction::Deposit(deposit),
+        Transaction::Withdrawal(withdrawal),
+        Transaction::Dispute(dispute),
+        Transaction::Resolve(resolve),
+    ] {
+        assert!(matches!(engine.apply(transaction), Ok(Outcome::Applied)));
+    }
+
+    let accounts = export(&engine);
+    let account = currency_account(&accounts, 1, "USD");
+    assert_eq!(account.available(), dec!(70));
+    assert_eq!(account.held(), dec!(0));
+    assert_eq!(
+        engine.transaction_state(1),
+        Some(TransactionState::Resolved)
+    );
+}
+
+#[test]
+fn test_apply_chargeback_locks_account() {
+    let mut engine = PaymentEngine::new();
+    let deposit = Deposit::new(1, 1, dec!(100), usd(), None).unwrap();
+    let dispute = Dispute::new(1, 1, None, Some(usd()), None).unwrap();
+    let chargeback = Chargeback::new(1, 1, None, Some(usd()), None).unwrap();
+
+    engine.apply(Transaction::Deposit(deposit)).unwrap();
+    engine.apply(Transaction::Dispute(dispute)).unwrap();
+    engine.apply(Transaction::Chargeback(chargeback)).unwrap();
+
+    let accounts = export(&engine);
+    let account = currency_account(&accounts, 1, "USD");
+    assert!(account.is_locked());
+    assert_eq!(account.total(), dec!(0));
+}
+
+#[test]
+fn test_apply_returns_processing_errors() {
+    let mut engine = PaymentEngine::new();
+    let withdrawal = Withdrawal::new(1, 1, dec!(10), usd(), None).unwrap();
+    let dispute = Dispute::new(1, 7, None, Some(usd()), None).unwrap();
+
+    assert!(matches!(
+        engine.apply(Transaction::Withdrawal(withdrawal)),
+        Err(ProcessingError::AccountNotFound { client: 1 })
+    ));
+    assert!(matches!(
+        engine.apply(Transaction::Dispute(dispute)),
+        Err(ProcessingError::TransactionNotFound { tx: 7 })
+    ));
+}
+
+#[test]
+fn test_apply_reports_ignored_replays() {
+    let mut engine = PaymentEngine::builder().idempotent_replays(true).build();
+    let deposit = Deposit::new(1, 1, dec!(100), usd(), None).unwrap();
+
+    assert!(matches!(
+        engine.apply(Transaction::Deposit(deposit.clone())),
+        Ok(Outcome::Applied)
+    ));
+    assert!(matches!(
+        engine.apply(Transaction::Deposit(deposit)),
+        Ok(Outcome::Replayed)
+    ));
+    assert_eq!(
+        currency_account(&export(&engine), 1, "USD").total(),
+        dec!(100)
+    );
+}
+
+#[test]
+fn test_typed_constructors_reject_invalid_amounts() {
+    assert!(Deposit::new(1, 1, dec!(0), usd(), None).is_err());
+    assert!(Withdrawal::new(1, 1, dec!(1.00001), usd(), None).is_err());
+    assert!(Resolve::new(1, 1, Some(dec!(-5)), Some(usd()), None).is_err());
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+#[test]
+fn test_get_account_per_currency() {
+    let input = "type,client,tx,amount,currency
+deposit,1,1,100.0,USD
+deposit,1,2,5000,JPY
+deposit,2,3,50.0,USD";
+    let engine = process_with_engine(input, PaymentEngine::builder());
+
+    let jpy: Currency = "JPY".parse().unwrap();
+    let account = engine.get_account(1, jpy).unwrap();
+    assert_eq!(account.available(), dec!(5000));
+    assert!(engine.get_account(2, jpy).is_none());
+    assert!(engine.get_account(3, usd()).is_none());
+
+    let currencies: Vec<_> = engine.client_accounts(1).map(Account::currency).collect();
+    assert_eq!(currencies, vec![jpy, usd()]);
+    assert_eq!(engine.accounts().count(), 3);
+    assert_eq!(
+        engine.accounts().map(Account::total).sum::<Decimal>(),
+        dec!(5150)
+    );
+}
+
+#[test]
+fn test_get_transaction_with_dispute_status() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+dispute,1,1,40.0
+withdrawal,1,2,10.0";
+    let engine = process_with_engine(input, PaymentEngine::builder());
+
+    let retained = engine.get_transaction(1).unwrap();
+    assert_eq!(retained.client_id(), 1);
+    assert_eq!(retained.state(), TransactionState::Disputed);
+    assert_eq!(retained.disputed(), dec!(40));
+    let Transaction::Deposit(deposit) = retained.transaction() else {
+        panic!("expected a deposit, got {}", retained.transaction());
+    };
+    assert_eq!(deposit.amount(), dec!(100));
+
+    assert_eq!(
+        engine.get_transaction(2).map(|retained| retained.state()),
+        Some(TransactionState::Applied)
+    );
+    assert!(engine.get_transaction(3).is_none());
+}
+
+#[test]
+fn test_open_disputes_per_client() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,2,50.0
+deposit,1,3,25.0
+deposit,2,4,10.0
+dispute,1,3,
+dispute,1,1,
+dispute,1,2,
+resolve,1,2,
+dispute,2,4,";
+    let engine = process_with_engine(input, PaymentEngine::builder());
+
+    let disputes = engine.open_disputes(1);
+    let ids: Vec<_> = disputes
+        .iter()
+        .map(|dispute| dispute.transaction().transaction_id())
+        .collect();
+    assert_eq!(ids, vec![Some(1), Some(3)]);
+    assert!(disputes
+        .iter()
+        .all(|dispute| dispute.state() == TransactionState::Disputed));
+    assert_eq!(
+        disputes
+            .iter()
+            .map(RetainedTransaction::disputed)
+            .sum::<Decimal>(),
+        dec!(125)
+    );
+    assert_eq!(engine.open_disputes(2).len(), 1);
+    assert!(engine.open_disputes(3).is_empty());
+}
+
+// ============================================================================
+// Processing Report
+// ============================================================================
+
+fn report_for(input: &str, builder: PaymentEngineBuilder) -> ProcessingReport {
+    let mut engine = builder.build();
+    engine.process_transactions(Cursor::new(input)).unwrap()
+}
+
+#[test]
+fn test_report_counts_rows_by_type_and_outcome() {
+    let input = "type,client,tx,amount,to
+deposit,1,1,100.0,
+deposit,2,2,50.0,
+withdrawal,1,3,30.0,
+transfer,2,4,20.0,3
+withdrawal,2,5,500.0,
+dispute,1,99,,";
+    let report = report_for(input, PaymentEngine::builder());
+
+    assert_eq!(report.rows(), 6);
+    assert_eq!(report.applied(), 4);
+    assert_eq!(report.rejected(), 2);
+    assert_eq!(report.rows_of_type(TransactionType::Deposit), 2);
+    assert_eq!(report.rows_of_type(TransactionType::Withdrawal), 2);
+    assert_eq!(report.rows_of_type(TransactionType::Chargeback), 0);
+    assert_eq!(report.errors_of_kind("insufficient_funds"), 1);
+    assert_eq!(report.errors_of_kind("transaction_not_found"), 1);
+
+    let usd = Currency::default();
+    assert_eq!(report.amount(TransactionType::Deposit, usd), dec!(150));
+    assert_eq!(report.amount(TransactionType::Withdrawal, usd), dec!(30));
+    assert_eq!(report.amount(TransactionType::Transfer, usd), dec!(20));
+    assert_eq!(
+        report.clients().iter().copied().collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+}
+
+#[test]
+fn test_report_lists_rejected_rows_with_record_and_reason() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+withdrawal,1,2,500.0
+deposit,1,1,100.0";
+    let report = report_for(input, PaymentEngine::builder());
+
+    let rejected = report.rejected_rows();
+    assert_eq!(rejected.len(), 2);
+    assert_eq!(rejected[0].row(), 2);
+    assert_eq!(rejected[0].kind(), "insufficient_funds");
+    assert_eq!(rejected[0].record().amount, Some(dec!(500)));
+    assert!(rejected[0].reason().starts_with("Insufficient funds"));
+    assert_eq!(rejected[1].row(), 3);
+    assert_eq!(rejected[1].kind(), "duplicate_transaction");
+}
+
+#[test]
+fn test_report_counts_replays_apart() {
+    let input = "type,client,tx,amount
+deposit,1,1,100.0
+deposit,1,1,100.0";
+    let report = report_for(input, PaymentEngine::builder().idempotent_replays(true));
+
+    assert_eq!(report.applied(), 1);
+    assert_eq!(report.replayed(), 1);
+    assert_eq!(report.rejected(), 0);
+    assert_eq!(
+        report.amount(TransactionType::Deposit, Currency::default()),
+        dec!(100)
+    );
+}
+
+#[test]
+fn test_report_includes_reorder_buffer_rows() {
+    let input = "type,client,tx,amount
+dispute,1,1,
+dispute,1,7,
+deposit,1,1,100.0";
+    let report = report_for(input, PaymentEngine::builder().reorder_window_rows(5));
+
+    assert_eq!(report.rows(), 3);
+    assert_eq!(report.applied(), 2);
+    assert_eq!(report.rejected(), 1);
+    let rejected = &report.rejected_rows()[0];
+    assert_eq!(rejected.row(), 2);
+    assert_eq!(rejected.kind(), "transaction_not_found");
+    assert_eq!(rejected.reason(), "Transaction 7 not found, end of input");
+}